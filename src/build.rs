@@ -1,24 +1,181 @@
+use std::fmt;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::i32;
+use std::i64;
+
+use rustc_serialize::json::Json;
 
 use Data;
-use Data::{Strng, Bool, Integer, Float, Vector, Hash, Lambda};
+use OrderedMap;
+use RustacheResult;
+use RustacheError::JsonError;
+use rustache::render_string_from_hash;
+use template::{EscapeMode, NullMode, FinalNewline, TagKind, copy_data, default_truthiness};
+use Data::{Strng, Bool, Integer, Float, Vector, Hash, Lambda, DataLambda, Null};
+
+/// `KeyDiff` describes how a top-level key differs between two `HashBuilder`s
+#[derive(Debug, PartialEq)]
+pub enum KeyDiff {
+    /// The key is only present in the other builder
+    Added(String),
+    /// The key is only present in this builder
+    Removed(String),
+    /// The key is present in both builders with different data
+    Changed(String)
+}
 
-/// `HashBuilder` is a helper type that constructs `Data` types in a HashMap
-#[derive(Debug)]
+/// `HashBuilder` is a helper type that constructs `Data` types in an `OrderedMap`
 pub struct HashBuilder<'a> {
     #[doc(hidden)]
-    pub data: HashMap<String, Data<'a>>,
+    pub data: OrderedMap<Data<'a>>,
+    #[doc(hidden)]
+    pub partials_path: &'a str,
+    #[doc(hidden)]
+    pub strict_variables: bool,
+    #[doc(hidden)]
+    pub strict_sections: bool,
+    #[doc(hidden)]
+    pub escape_mode: Option<EscapeMode>,
+    #[doc(hidden)]
+    pub escape_fn: Option<Box<Fn(&str) -> String + 'a>>,
+    #[doc(hidden)]
+    pub escape_slash: bool,
+    #[doc(hidden)]
+    pub null_rendering: NullMode,
+    #[doc(hidden)]
+    pub max_output_bytes: Option<usize>,
+    #[doc(hidden)]
+    pub float_precision: Option<usize>,
     #[doc(hidden)]
-    pub partials_path: &'a str
+    pub escaped_entities: Vec<String>,
+    #[doc(hidden)]
+    pub html_minify: bool,
+    #[doc(hidden)]
+    pub final_newline: FinalNewline,
+    #[doc(hidden)]
+    pub production: bool,
+    #[doc(hidden)]
+    pub empty_strings_falsy: bool,
+    #[doc(hidden)]
+    pub on_tag: Option<RefCell<&'a mut FnMut(&str, TagKind)>>,
+    #[doc(hidden)]
+    pub transform: Option<RefCell<&'a mut FnMut(&str) -> String>>,
+    #[doc(hidden)]
+    pub catch_lambda_panics: bool,
+    #[doc(hidden)]
+    pub truthiness: fn(&Data) -> bool
+}
+
+// Implementing custom Debug for HashBuilder, since the hooks can't be shown
+impl<'a> fmt::Debug for HashBuilder<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HashBuilder")
+            .field("data", &self.data)
+            .field("partials_path", &self.partials_path)
+            .field("strict_variables", &self.strict_variables)
+            .field("strict_sections", &self.strict_sections)
+            .field("escape_mode", &self.escape_mode)
+            .field("escape_slash", &self.escape_slash)
+            .field("null_rendering", &self.null_rendering)
+            .field("max_output_bytes", &self.max_output_bytes)
+            .field("float_precision", &self.float_precision)
+            .field("escaped_entities", &self.escaped_entities)
+            .field("html_minify", &self.html_minify)
+            .field("final_newline", &self.final_newline)
+            .field("production", &self.production)
+            .field("empty_strings_falsy", &self.empty_strings_falsy)
+            .field("catch_lambda_panics", &self.catch_lambda_panics)
+            .finish()
+    }
+}
+
+// Lambdas and hooks hold closures, which can't be shared between
+// builders.  A clone leaves them out and copies everything else.
+impl<'a> Clone for HashBuilder<'a> {
+    fn clone(&self) -> HashBuilder<'a> {
+        let mut data = OrderedMap::new();
+        for (key, val) in self.data.iter() {
+            match copy_data(val) {
+                Some(val) => { data.insert(key.clone(), val); },
+                None => { }
+            }
+        }
+
+        HashBuilder {
+            data: data,
+            partials_path: self.partials_path,
+            strict_variables: self.strict_variables,
+            strict_sections: self.strict_sections,
+            escape_mode: self.escape_mode,
+            escape_fn: None,
+            escape_slash: self.escape_slash,
+            null_rendering: self.null_rendering,
+            max_output_bytes: self.max_output_bytes,
+            float_precision: self.float_precision,
+            escaped_entities: self.escaped_entities.clone(),
+            html_minify: self.html_minify,
+            final_newline: self.final_newline,
+            production: self.production,
+            empty_strings_falsy: self.empty_strings_falsy,
+            on_tag: None,
+            transform: None,
+            catch_lambda_panics: self.catch_lambda_panics,
+            truthiness: self.truthiness
+        }
+    }
+}
+
+impl<'a> Default for HashBuilder<'a> {
+    fn default() -> HashBuilder<'a> {
+        HashBuilder::new()
+    }
 }
 
 impl<'a> HashBuilder<'a> {
     /// Create a new `HashBuilder` instance
     pub fn new() -> HashBuilder<'a> {
         HashBuilder {
-            data: HashMap::new(),
-            partials_path: ""
+            data: OrderedMap::new(),
+            partials_path: "",
+            strict_variables: false,
+            strict_sections: false,
+            escape_mode: None,
+            escape_fn: None,
+            escape_slash: false,
+            null_rendering: NullMode::Empty,
+            max_output_bytes: None,
+            float_precision: None,
+            escaped_entities: Vec::new(),
+            html_minify: false,
+            final_newline: FinalNewline::Keep,
+            production: false,
+            empty_strings_falsy: false,
+            on_tag: None,
+            transform: None,
+            catch_lambda_panics: false,
+            truthiness: default_truthiness
+        }
+    }
+
+    /// Create a `HashBuilder` from a string holding a JSON object.  Objects
+    /// become hashes, arrays become vectors, and numbers become integers
+    /// or floats.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::from_json_str(r#"{"name": "Anduin", "age": 21}"#).unwrap();
+    /// ```
+    pub fn from_json_str(json: &str) -> RustacheResult<HashBuilder<'a>> {
+        match Json::from_str(json) {
+            Ok(Json::Object(object)) => {
+                let mut builder = HashBuilder::new();
+                for (key, value) in object.into_iter() {
+                    builder.insert_data(key, json_to_data(value));
+                }
+                Ok(builder)
+            },
+            Ok(_) => Err(JsonError("Invalid JSON. Expected an object".to_string())),
+            Err(err) => Err(JsonError(format!("Invalid JSON. {}", err)))
         }
     }
 
@@ -29,10 +186,9 @@ impl<'a> HashBuilder<'a> {
     /// let data = HashBuilder::new()
     ///     .insert_string("game", "Hearthstone: Heroes of Warcraft");
     /// ```
-    pub fn insert_string<K: ToString, V: ToString>(self, key: K, value: V) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
-        data.insert(key.to_string(), Strng(value.to_string()));
-        HashBuilder { data: data, partials_path: partials_path }
+    pub fn insert_string<K: ToString, V: ToString>(mut self, key: K, value: V) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), Strng(value.to_string()));
+        self
     }
 
     /// Add a `Boolean` to the `HashBuilder`
@@ -42,10 +198,9 @@ impl<'a> HashBuilder<'a> {
     /// let data = HashBuilder::new()
     ///     .insert_bool("playing", true);
     /// ```
-    pub fn insert_bool<K: ToString>(self, key: K, value: bool) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
-        data.insert(key.to_string(), Bool(value));
-        HashBuilder { data: data, partials_path: partials_path }
+    pub fn insert_bool<K: ToString>(mut self, key: K, value: bool) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), Bool(value));
+        self
     }
 
     /// Add an `Integer` to the `HashBuilder`
@@ -56,10 +211,9 @@ impl<'a> HashBuilder<'a> {
     ///     .insert_int("age", 10i32)
     ///     .insert_int("drinking age", -21i32);
     /// ```
-    pub fn insert_int<K: ToString>(self, key: K, value: i32) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
-        data.insert(key.to_string(), Integer(value));
-        HashBuilder { data: data, partials_path: partials_path }
+    pub fn insert_int<K: ToString>(mut self, key: K, value: i32) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), Integer(value));
+        self
     }
 
     /// Add a `Float` to the `HashBuilder`
@@ -70,10 +224,9 @@ impl<'a> HashBuilder<'a> {
     ///     .insert_float("pi", 3.141596f64)
     ///     .insert_float("phi", 1.61803398875f64);
     /// ```
-    pub fn insert_float<K: ToString>(self, key: K, value: f64) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
-        data.insert(key.to_string(), Float(value));
-        HashBuilder { data: data, partials_path: partials_path }
+    pub fn insert_float<K: ToString>(mut self, key: K, value: f64) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), Float(value));
+        self
     }
 
     /// Add a `Vector` to the `HashBuilder`
@@ -87,11 +240,25 @@ impl<'a> HashBuilder<'a> {
     ///             .push_string("Druid".to_string())
     ///     });
     /// ```
-    pub fn insert_vector<F: Fn(VecBuilder<'a>) -> VecBuilder<'a>, K: ToString>(self, key: K, f: F) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
+    pub fn insert_vector<F: Fn(VecBuilder<'a>) -> VecBuilder<'a>, K: ToString>(mut self, key: K, f: F) -> HashBuilder<'a> {
         let builder = f(VecBuilder::new());
-        data.insert(key.to_string(), builder.build());
-        HashBuilder { data: data, partials_path: partials_path }
+        self.insert_data(key.to_string(), builder.build());
+        self
+    }
+
+    /// Add a `Vector` to the `HashBuilder` from anything iterable whose
+    /// items convert into data, like strings, bools and numbers
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_vector_iter("levels", vec![1, 2, 3]);
+    /// ```
+    pub fn insert_vector_iter<K: ToString, I>(mut self, key: K, iter: I) -> HashBuilder<'a>
+        where I: IntoIterator, I::Item: Into<Data<'a>> {
+        let data = iter.into_iter().map(|item| item.into()).collect();
+        self.insert_data(key.to_string(), Vector(data));
+        self
     }
 
     /// Add a `Hash` to the `HashBuilder`
@@ -110,11 +277,26 @@ impl<'a> HashBuilder<'a> {
     ///             .insert_string("last_name", "Proudmoore")
     ///     });
     /// ```
-    pub fn insert_hash<F: Fn(HashBuilder<'a>) -> HashBuilder<'a>, K: ToString>(self, key: K, f: F) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
+    pub fn insert_hash<F: Fn(HashBuilder<'a>) -> HashBuilder<'a>, K: ToString>(mut self, key: K, f: F) -> HashBuilder<'a> {
         let builder = f(HashBuilder::new());
-        data.insert(key.to_string(), builder.build());
-        HashBuilder { data: data, partials_path: partials_path }
+        self.insert_data(key.to_string(), builder.build());
+        self
+    }
+
+    /// Render a template with its own data and add the output to the
+    /// `HashBuilder` as a `String`.  The output is already escaped, so it
+    /// should be written out with an unescaped tag like `{{{ name }}}`.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let hero = HashBuilder::new().insert_string("name", "Anduin");
+    /// let data = HashBuilder::new()
+    ///     .insert_rendered("card", "<b>{{ name }}</b>", &hero).unwrap();
+    /// ```
+    pub fn insert_rendered<K: ToString>(mut self, key: K, template: &str, data: &HashBuilder) -> RustacheResult<HashBuilder<'a>> {
+        let rendered = try!(render_string_from_hash(template, data));
+        self.insert_data(key.to_string(), Strng(rendered));
+        Ok(self)
     }
 
     /// Add a `Lambda` that accepts a String and returns a String to the `HashBuilder`
@@ -125,15 +307,424 @@ impl<'a> HashBuilder<'a> {
     /// let data = HashBuilder::new()
     ///     .insert_lambda("lambda", &mut f);
     /// ```
-    pub fn insert_lambda<K: ToString>(self, key: K, f: &'a mut FnMut(String) -> String) -> HashBuilder<'a> {
-        let HashBuilder { mut data, partials_path } = self;
-        data.insert(key.to_string(), Lambda(RefCell::new(f)));
-        HashBuilder { data: data, partials_path: partials_path }
+    pub fn insert_lambda<K: ToString>(mut self, key: K, f: &'a mut FnMut(String) -> String) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), Lambda(RefCell::new(f)));
+        self
+    }
+
+    /// Add a `Lambda` that accepts a String and returns `Data` to the
+    /// `HashBuilder`.  The returned data is rendered as if it had been
+    /// inserted under the key, so a vector or hash can drive a section.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Data};
+    /// let mut f = |_| { Data::Vector(vec![Data::from("Mage"), Data::from("Druid")]) };
+    /// let data = HashBuilder::new()
+    ///     .insert_data_lambda("classes", &mut f);
+    /// ```
+    pub fn insert_data_lambda<K: ToString>(mut self, key: K, f: &'a mut FnMut(String) -> Data<'a>) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), DataLambda(RefCell::new(f)));
+        self
+    }
+
+    /// Add the current local time to the `HashBuilder` as a `String`, formatted
+    /// with a `strftime` style format.  An empty or invalid format falls back
+    /// to the default of `%Y-%m-%d %H:%M:%S`.  Requires the `time` feature.
+    ///
+    /// ```ignore
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_now("generated_at", "%d %B %Y");
+    /// ```
+    #[cfg(feature = "time")]
+    pub fn insert_now<K: ToString>(self, key: K, fmt: &str) -> HashBuilder<'a> {
+        let now = ::time::now();
+        let text = match now.strftime(fmt) {
+            Ok(ref formatted) if !fmt.is_empty() => formatted.to_string(),
+            _ => now.strftime("%Y-%m-%d %H:%M:%S").unwrap().to_string()
+        };
+        self.insert_string(key, text)
+    }
+
+    /// Add a `Null` to the `HashBuilder`
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_null("nickname");
+    /// ```
+    pub fn insert_null<K: ToString>(mut self, key: K) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), Null);
+        self
+    }
+
+    /// Add an optional value to the `HashBuilder`, as a `Null` when it is
+    /// `None`.  Strings, bools and numbers can be given.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let nickname: Option<&str> = None;
+    /// let data = HashBuilder::new()
+    ///     .insert_option("nickname", nickname)
+    ///     .insert_option("age", Some(21));
+    /// ```
+    pub fn insert_option<K: ToString, T: Into<Data<'a>>>(mut self, key: K, value: Option<T>) -> HashBuilder<'a> {
+        let data = match value {
+            Some(value) => value.into(),
+            None => Null
+        };
+        self.insert_data(key.to_string(), data);
+        self
+    }
+
+    /// Add an already built `Data` value to the `HashBuilder`
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Data};
+    /// let data = HashBuilder::new()
+    ///     .insert("classes", Data::Vector(vec![Data::Strng("Mage".to_string())]));
+    /// ```
+    pub fn insert<K: ToString>(mut self, key: K, value: Data<'a>) -> HashBuilder<'a> {
+        self.insert_data(key.to_string(), value);
+        self
     }
 
     /// Set a path to partials data
-    pub fn set_partials_path(self, path: &'a str) -> HashBuilder<'a> {
-        HashBuilder { data: self.data, partials_path: path }
+    ///
+    /// Partial names may use either `/` or `\` between directories, and are
+    /// joined onto this path with the platform's separator.  Names are
+    /// otherwise used as-is, so whether `{{> Header }}` finds `header` is up
+    /// to the filesystem: case-sensitive on most Unix systems, not on Windows.
+    pub fn set_partials_path(mut self, path: &'a str) -> HashBuilder<'a> {
+        self.partials_path = path;
+        self
+    }
+
+    /// Make rendering fail when a variable tag has no data, instead of
+    /// rendering it as an empty string
+    pub fn set_strict_variables(mut self, strict: bool) -> HashBuilder<'a> {
+        self.strict_variables = strict;
+        self
+    }
+
+    /// Make rendering fail when a section or inverted section tag has no data,
+    /// instead of treating the missing key as falsy
+    pub fn set_strict_sections(mut self, strict: bool) -> HashBuilder<'a> {
+        self.strict_sections = strict;
+        self
+    }
+
+    /// Set how the output of `{{ value }}` tags is escaped.  When not set, the
+    /// mode from `rustache::set_default_escape_mode` is used, HTML by default.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, EscapeMode};
+    /// let data = HashBuilder::new()
+    ///     .set_escape_mode(EscapeMode::Markdown);
+    /// ```
+    pub fn set_escape_mode(mut self, mode: EscapeMode) -> HashBuilder<'a> {
+        self.escape_mode = Some(mode);
+        self
+    }
+
+    /// Set a function to escape the output of `{{ value }}` tags with, in
+    /// place of the escape mode.  `{{& value }}` tags are still not escaped.
+    /// Like the other hooks, it is left out of a clone.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// fn escape_quotes(input: &str) -> String {
+    ///     input.replace("\"", "\\\"")
+    /// }
+    /// let data = HashBuilder::new()
+    ///     .set_escape_fn(escape_quotes);
+    /// ```
+    pub fn set_escape_fn<F: Fn(&str) -> String + 'a>(mut self, escape: F) -> HashBuilder<'a> {
+        self.escape_fn = Some(Box::new(escape));
+        self
+    }
+
+    /// Set whether HTML escaping also turns `/` into `&#x2F;`, so that a
+    /// value can't close an inline `<script>`.  Off by default.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_escape_slash(true);
+    /// ```
+    pub fn set_escape_slash(mut self, escape: bool) -> HashBuilder<'a> {
+        self.escape_slash = escape;
+        self
+    }
+
+    /// Set whether a `Null` in a `{{ value }}` tag renders as an empty string
+    /// (the default) or as the literal text `null`
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, NullMode};
+    /// let data = HashBuilder::new()
+    ///     .set_null_rendering(NullMode::Literal);
+    /// ```
+    pub fn set_null_rendering(mut self, mode: NullMode) -> HashBuilder<'a> {
+        self.null_rendering = mode;
+        self
+    }
+
+    /// Limit how many bytes a render may write.  Rendering stops with an
+    /// error once the output would go past the limit.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_max_output_bytes(1024);
+    /// ```
+    pub fn set_max_output_bytes(mut self, limit: usize) -> HashBuilder<'a> {
+        self.max_output_bytes = Some(limit);
+        self
+    }
+
+    /// Set how many decimal places floats are written with.  By default a
+    /// float is written with as many places as it needs.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_float("price", 3.14159)
+    ///     .set_float_precision(2);
+    /// ```
+    pub fn set_float_precision(mut self, places: usize) -> HashBuilder<'a> {
+        self.float_precision = Some(places);
+        self
+    }
+
+    /// Set the entities that HTML escaping treats as already escaped, so
+    /// their `&` is left alone instead of becoming `&amp;`.  This makes
+    /// escaping idempotent for those entities.  Any other `&` is still escaped.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_escaped_entities(&["&nbsp;", "&amp;"]);
+    /// ```
+    pub fn set_escaped_entities(mut self, entities: &[&str]) -> HashBuilder<'a> {
+        self.escaped_entities = entities.iter().map(|entity| entity.to_string()).collect();
+        self
+    }
+
+    /// Set how newlines at the end of the rendered output are handled: kept
+    /// as they are (the default), made into exactly one, or removed
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, FinalNewline};
+    /// let data = HashBuilder::new()
+    ///     .set_final_newline(FinalNewline::Ensure);
+    /// ```
+    pub fn set_final_newline(mut self, policy: FinalNewline) -> HashBuilder<'a> {
+        self.final_newline = policy;
+        self
+    }
+
+    /// Set whether rendered output is minified as HTML: comments and the
+    /// whitespace next to block element tags are removed, other whitespace
+    /// is collapsed to a single space, and `<pre>`, `<textarea>`, `<script>`
+    /// and `<style>` contents are left alone
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_html_minify(true);
+    /// ```
+    pub fn set_html_minify(mut self, minify: bool) -> HashBuilder<'a> {
+        self.html_minify = minify;
+        self
+    }
+
+    /// Set whether this is a production render.  Production renders drop
+    /// everything between `{{!-- dev-only --}}` and `{{!--/dev-only--}}`,
+    /// other renders include it as if the markers were plain comments.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_production(true);
+    /// ```
+    pub fn set_production(mut self, production: bool) -> HashBuilder<'a> {
+        self.production = production;
+        self
+    }
+
+    /// Catch a panic in a lambda while rendering, and fail the render with
+    /// a `LambdaPanicked` error naming the lambda instead of unwinding
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_catch_lambda_panics(true);
+    /// ```
+    pub fn set_catch_lambda_panics(mut self, catch: bool) -> HashBuilder<'a> {
+        self.catch_lambda_panics = catch;
+        self
+    }
+
+    /// Set whether an empty string counts as false for sections, so that
+    /// `{{# name }}` is skipped and `{{^ name }}` renders when `name` is "".
+    /// By default, as in the spec, an empty string is true like any other.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .set_empty_strings_falsy(true);
+    /// ```
+    pub fn set_empty_strings_falsy(mut self, falsy: bool) -> HashBuilder<'a> {
+        self.empty_strings_falsy = falsy;
+        self
+    }
+
+    /// Set the test for whether a section renders for its data, in place
+    /// of `rustache::default_truthiness`.  Inverted sections render when
+    /// it returns false.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Data};
+    /// fn truthy(data: &Data) -> bool {
+    ///     match *data {
+    ///         Data::Strng(ref val) => val != "false",
+    ///         _ => rustache::default_truthiness(data)
+    ///     }
+    /// }
+    /// let data = HashBuilder::new()
+    ///     .set_truthiness(truthy);
+    /// ```
+    pub fn set_truthiness(mut self, truthiness: fn(&Data) -> bool) -> HashBuilder<'a> {
+        self.truthiness = truthiness;
+        self
+    }
+
+    /// Set a hook that is called with the name and kind of each variable,
+    /// section and partial tag just before it is rendered
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, TagKind};
+    /// let mut f = |name: &str, kind: TagKind| { println!("{:?} {}", kind, name); };
+    /// let data = HashBuilder::new()
+    ///     .set_on_tag(&mut f);
+    /// ```
+    pub fn set_on_tag(mut self, f: &'a mut FnMut(&str, TagKind)) -> HashBuilder<'a> {
+        self.on_tag = Some(RefCell::new(f));
+        self
+    }
+
+    /// Set a function that every interpolated string value is passed
+    /// through before it is escaped and written out
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let mut upper = |val: &str| val.to_uppercase();
+    /// let data = HashBuilder::new()
+    ///     .set_transform(&mut upper);
+    /// ```
+    pub fn set_transform(mut self, f: &'a mut FnMut(&str) -> String) -> HashBuilder<'a> {
+        self.transform = Some(RefCell::new(f));
+        self
+    }
+
+    /// Add all of the data in another `HashBuilder` to this one.  Where both
+    /// have the same key, the other builder's data wins.  Settings like the
+    /// partials path are kept from this builder.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let site = HashBuilder::new()
+    ///     .insert_string("site", "Hearthstone")
+    ///     .insert_string("title", "Home");
+    /// let page = HashBuilder::new()
+    ///     .insert_string("title", "Heroes");
+    /// let data = site.merge(page);
+    /// assert_eq!(data.keys(), vec!["site", "title"]);
+    /// ```
+    pub fn merge(mut self, other: HashBuilder<'a>) -> HashBuilder<'a> {
+        self.data.extend(other.data);
+        self
+    }
+
+    /// Compare the top-level keys of this `HashBuilder` against another one.
+    /// Lambdas can't be compared, so two lambdas are always considered equal.
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, KeyDiff};
+    /// let before = HashBuilder::new().insert_string("name", "Anduin");
+    /// let after = HashBuilder::new().insert_string("name", "Jaina");
+    /// assert_eq!(before.diff(&after), vec![KeyDiff::Changed("name".to_string())]);
+    /// ```
+    pub fn diff(&self, other: &HashBuilder<'a>) -> Vec<KeyDiff> {
+        let mut diffs = Vec::new();
+
+        for (key, value) in self.data.iter() {
+            match other.data.get(key) {
+                None => diffs.push(KeyDiff::Removed(key.clone())),
+                Some(other_value) => {
+                    if !data_eq(value, other_value) {
+                        diffs.push(KeyDiff::Changed(key.clone()));
+                    }
+                }
+            }
+        }
+
+        for key in other.data.keys() {
+            if !self.data.contains_key(key) {
+                diffs.push(KeyDiff::Added(key.clone()));
+            }
+        }
+
+        // sort by key, so the diff doesn't depend on either builder's order
+        diffs.sort_by(|a, b| diff_key(a).cmp(diff_key(b)));
+        diffs
+    }
+
+    /// Return the top-level keys of the `HashBuilder` in the order they
+    /// were first inserted.  Replacing a key keeps its original position.
+    /// Nested hashes keep their order the same way, and `{{#each}}`
+    /// sections go through a hash's entries in this order.
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new()
+    ///     .insert_string("name", "Anduin")
+    ///     .insert_int("age", 21);
+    /// assert_eq!(data.keys(), vec!["name", "age"]);
+    /// ```
+    pub fn keys(&self) -> Vec<&str> {
+        self.data.keys().map(|key| &key[..]).collect()
+    }
+
+    /// Return the data stored under a top-level key, if there is any
+    ///
+    /// ```rust
+    /// use rustache::{HashBuilder, Data};
+    /// let data = HashBuilder::new().insert_int("age", 21);
+    /// assert_eq!(data.get("age"), Some(&Data::Integer(21)));
+    /// assert_eq!(data.get("name"), None);
+    /// ```
+    pub fn get(&self, key: &str) -> Option<&Data<'a>> {
+        self.data.get(key)
+    }
+
+    /// Return whether anything is stored under a top-level key
+    ///
+    /// ```rust
+    /// use rustache::HashBuilder;
+    /// let data = HashBuilder::new().insert_string("name", "Anduin");
+    /// assert!(data.contains_key("name"));
+    /// assert!(!data.contains_key("age"));
+    /// ```
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.data.contains_key(key)
+    }
+
+    // Stores the data under the key, a replaced key keeps its position
+    #[doc(hidden)]
+    pub fn insert_data(&mut self, key: String, value: Data<'a>) {
+        self.data.insert(key, value);
     }
 
     /// Return the built `Data`
@@ -142,11 +733,95 @@ impl<'a> HashBuilder<'a> {
     }
 }
 
+// compares two data values the way PartialEq does, but without
+// panicking when lambdas are found anywhere in the structure
+fn data_eq<'a>(a: &Data<'a>, b: &Data<'a>) -> bool {
+    match (a, b) {
+        (&Vector(ref val0), &Vector(ref val1)) => {
+            val0.len() == val1.len() && val0.iter().zip(val1.iter()).all(|(x, y)| data_eq(x, y))
+        },
+        (&Hash(ref val0), &Hash(ref val1)) => {
+            val0.len() == val1.len() && val0.iter().all(|(k, x)| {
+                match val1.get(k) {
+                    Some(y) => data_eq(x, y),
+                    None => false
+                }
+            })
+        },
+        (&Lambda(_), &Lambda(_)) => true,
+        (&Lambda(_), _) | (_, &Lambda(_)) => false,
+        (&DataLambda(_), &DataLambda(_)) => true,
+        (&DataLambda(_), _) | (_, &DataLambda(_)) => false,
+        (_, _) => a == b
+    }
+}
+
+fn diff_key(diff: &KeyDiff) -> &String {
+    match *diff {
+        KeyDiff::Added(ref key) | KeyDiff::Removed(ref key) | KeyDiff::Changed(ref key) => key
+    }
+}
+
+/// Converts JSON into `Data`.  Objects become hashes, arrays become vectors,
+/// `null` becomes `Null`, and numbers become integers, or floats when they
+/// don't fit in an `i32`.
+impl<'a> From<Json> for Data<'a> {
+    fn from(json: Json) -> Data<'a> {
+        json_to_data(json)
+    }
+}
+
+// converts parsed JSON into Data, numbers too big for an Integer become Floats
+fn json_to_data<'a>(json: Json) -> Data<'a> {
+    match json {
+        Json::I64(num) => integer_data(num),
+        Json::U64(num) if num <= i64::MAX as u64 => integer_data(num as i64),
+        Json::U64(num) => Float(num as f64),
+        Json::F64(num) => Float(num),
+        Json::String(text) => Strng(text),
+        Json::Boolean(val) => Bool(val),
+        Json::Array(list) => Vector(list.into_iter().map(json_to_data).collect()),
+        Json::Object(object) => {
+            let mut hash = OrderedMap::new();
+            for (key, value) in object.into_iter() {
+                hash.insert(key, json_to_data(value));
+            }
+            Hash(hash)
+        },
+        Json::Null => Null
+    }
+}
+
+// converts a whole number from JSON or YAML into Data, numbers too big
+// for an Integer become Floats
+pub fn integer_data<'a>(num: i64) -> Data<'a> {
+    if num >= i32::MIN as i64 && num <= i32::MAX as i64 {
+        Integer(num as i32)
+    } else {
+        Float(num as f64)
+    }
+}
+
 /// `VecBuilder` is a helper type that constructs `Data` types in a Vector
 pub struct VecBuilder<'a> {
     data: Vec<Data<'a>>
 }
 
+// Like HashBuilder, a clone leaves out any lambdas
+impl<'a> Clone for VecBuilder<'a> {
+    fn clone(&self) -> VecBuilder<'a> {
+        VecBuilder {
+            data: self.data.iter().filter_map(copy_data).collect()
+        }
+    }
+}
+
+impl<'a> Default for VecBuilder<'a> {
+    fn default() -> VecBuilder<'a> {
+        VecBuilder::new()
+    }
+}
+
 impl<'a> VecBuilder<'a> {
     /// Create a new `VecBuilder` instance
     pub fn new() -> VecBuilder<'a> {
@@ -266,6 +941,33 @@ impl<'a> VecBuilder<'a> {
         VecBuilder { data: data }
     }
 
+    /// Add a `Null` to the `VecBuilder`
+    ///
+    /// ```rust
+    /// use rustache::VecBuilder;
+    /// let data = VecBuilder::new()
+    ///     .push_null();
+    /// ```
+    pub fn push_null(self) -> VecBuilder<'a> {
+        let VecBuilder { mut data } = self;
+        data.push(Null);
+        VecBuilder { data: data }
+    }
+
+    /// Add an already built `Data` value to the `VecBuilder`
+    ///
+    /// ```rust
+    /// use rustache::{VecBuilder, Data};
+    /// let data = VecBuilder::new()
+    ///     .push(Data::Integer(10))
+    ///     .push(Data::Strng("Druid".to_string()));
+    /// ```
+    pub fn push(self, value: Data<'a>) -> VecBuilder<'a> {
+        let VecBuilder { mut data } = self;
+        data.push(value);
+        VecBuilder { data: data }
+    }
+
     /// Return the built `Data`
     fn build(self) -> Data<'a> {
         Vector(self.data)
@@ -274,30 +976,61 @@ impl<'a> VecBuilder<'a> {
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
+    use std::cell::RefCell;
+    use std::collections::BTreeMap;
+    use OrderedMap;
+
+    use rustc_serialize::json::Json;
 
-    use {HashBuilder, VecBuilder};
-    use Data::{Strng, Bool, Integer, Float, Vector, Hash, Lambda};
+    use {HashBuilder, VecBuilder, KeyDiff, Data, render_string_from_hash};
+    use RustacheError::JsonError;
+    use Data::{Strng, Bool, Integer, Float, Vector, Hash, Lambda, Null};
 
     #[test]
     fn test_new_builders() {
-        assert_eq!(HashBuilder::new().build(), Hash(HashMap::new()));
+        assert_eq!(HashBuilder::new().build(), Hash(OrderedMap::new()));
         assert_eq!(VecBuilder::new().build(), Vector(Vec::new()));
     }
 
+    #[test]
+    fn test_default_builders() {
+        fn make<T: Default>() -> T {
+            T::default()
+        }
+
+        let hash: HashBuilder = make();
+        assert_eq!(format!("{:?}", hash), format!("{:?}", HashBuilder::new()));
+        assert_eq!("".to_string(), render_string_from_hash("", &hash).unwrap());
+        assert_eq!("[]".to_string(), render_string_from_hash("[{{name}}]", &hash).unwrap());
+
+        let vec: VecBuilder = make();
+        assert_eq!(vec.build(), Vector(Vec::new()));
+    }
+
     #[test]
     fn test_set_partials_path() {
         let hash = HashBuilder::new().set_partials_path("/path");
         assert_eq!(hash.partials_path, "/path");
     }
 
+    #[test]
+    fn test_set_strict_flags() {
+        let hash = HashBuilder::new();
+        assert_eq!(hash.strict_variables, false);
+        assert_eq!(hash.strict_sections, false);
+
+        let hash = hash.set_strict_variables(true).set_strict_sections(true);
+        assert_eq!(hash.strict_variables, true);
+        assert_eq!(hash.strict_sections, true);
+    }
+
     #[test]
     fn test_builders() {
-        let mut hearthstone = HashMap::new();
+        let mut hearthstone = OrderedMap::new();
         hearthstone.insert("name".to_string(), Strng("Hearthstone: Heroes of Warcraft".to_string()));
         hearthstone.insert("release_date".to_string(), Strng("December, 2014".to_string()));
 
-        let mut hash1 = HashMap::new();
+        let mut hash1 = OrderedMap::new();
         hash1.insert("first_name".to_string(), Strng("Anduin".to_string()));
         hash1.insert("last_name".to_string(), Strng("Wrynn".to_string()));
         hash1.insert("age".to_string(), Integer(21i32));
@@ -329,6 +1062,345 @@ mod tests {
         assert_eq!(hash2.partials_path, "/hearthstone");
     }
 
+    #[test]
+    fn test_nested_hash_builders() {
+        let mut city = OrderedMap::new();
+        city.insert("name".to_string(), Strng("Stormwind".to_string()));
+        let mut kingdom = OrderedMap::new();
+        kingdom.insert("name".to_string(), Strng("Azeroth".to_string()));
+        kingdom.insert("capital".to_string(), Hash(city));
+
+        let hash = HashBuilder::new()
+            .insert_hash("kingdom", |builder| {
+                builder
+                    .insert_string("name", "Azeroth")
+                    .insert_hash("capital", |builder| {
+                        builder.insert_string("name", "Stormwind")
+                    })
+            });
+
+        assert_eq!(hash.data.get("kingdom"), Some(&Hash(kingdom)));
+    }
+
+    #[test]
+    fn test_vector_of_hashes_builder() {
+        let mut anduin = OrderedMap::new();
+        anduin.insert("name".to_string(), Strng("Anduin".to_string()));
+        let mut jaina = OrderedMap::new();
+        jaina.insert("name".to_string(), Strng("Jaina".to_string()));
+        jaina.insert("spells".to_string(), Vector(vec!(Strng("Frostbolt".to_string()))));
+
+        let hash = HashBuilder::new()
+            .insert_vector("heroes", |builder| {
+                builder
+                    .push_hash(|builder| { builder.insert_string("name", "Anduin") })
+                    .push_hash(|builder| {
+                        builder
+                            .insert_string("name", "Jaina")
+                            .insert_vector("spells", |builder| { builder.push_string("Frostbolt") })
+                    })
+            });
+
+        assert_eq!(hash.data.get("heroes"), Some(&Vector(vec!(Hash(anduin), Hash(jaina)))));
+    }
+
+    #[test]
+    fn test_push_hash_records() {
+        let hash = HashBuilder::new()
+            .insert_vector("people", |builder| {
+                builder
+                    .push_hash(|builder| { builder.insert_string("name", "Anduin").insert_int("age", 21) })
+                    .push_hash(|builder| { builder.insert_string("name", "Varian").insert_int("age", 45) })
+            });
+
+        let rv = render_string_from_hash("<ul>{{#people}}<li>{{name}} ({{age}})</li>{{/people}}</ul>", &hash);
+
+        assert_eq!("<ul><li>Anduin (21)</li><li>Varian (45)</li></ul>".to_string(), rv.unwrap());
+    }
+
+    #[test]
+    fn test_clone_builders() {
+        let mut f = |_| { "world".to_string() };
+        let base = HashBuilder::new()
+            .set_partials_path("/views")
+            .insert_string("site", "Hearthstone")
+            .insert_hash("hero", |h| { h.insert_string("name", "Anduin") })
+            .insert_lambda("lambda", &mut f);
+
+        let request = base.clone()
+            .insert_string("site", "Warcraft")
+            .insert_string("user", "Jaina");
+
+        assert_eq!(base.data.get("site"), Some(&Strng("Hearthstone".to_string())));
+        assert_eq!(base.data.get("user"), None);
+        assert_eq!(base.keys(), vec!["site", "hero", "lambda"]);
+        assert_eq!(request.data.get("site"), Some(&Strng("Warcraft".to_string())));
+        assert_eq!(request.data.get("hero"), base.data.get("hero"));
+        assert_eq!(request.partials_path, "/views");
+        // lambdas aren't cloned
+        assert_eq!(request.keys(), vec!["site", "hero", "user"]);
+
+        let vec = VecBuilder::new().push_string("a");
+        assert_eq!(vec.clone().push_string("b").build(), Vector(vec!(Strng("a".to_string()), Strng("b".to_string()))));
+        assert_eq!(vec.build(), Vector(vec!(Strng("a".to_string()))));
+    }
+
+    #[test]
+    fn test_numeric_builders() {
+        let hash = HashBuilder::new()
+            .insert_int("age", 21)
+            .insert_float("weight", 120.16);
+
+        assert_eq!(hash.data.get("age"), Some(&Integer(21i32)));
+        assert_eq!(hash.data.get("weight"), Some(&Float(120.16f64)));
+    }
+
+    #[test]
+    fn test_numeric_vec_builder() {
+        let vec = VecBuilder::new()
+            .push_int(1)
+            .push_float(2.5)
+            .push_int(3);
+
+        assert_eq!(vec.build(), Vector(vec!(Integer(1i32), Float(2.5f64), Integer(3i32))));
+    }
+
+    #[test]
+    fn test_vector_iter_builder() {
+        let hash = HashBuilder::new()
+            .insert_vector_iter("levels", (1..4).map(|level| level * 10))
+            .insert_vector_iter("mixed", vec![Data::from(1), Data::from(2.5), Data::from("three"), Data::from(true)]);
+
+        assert_eq!(hash.data.get("levels"), Some(&Vector(vec!(Integer(10), Integer(20), Integer(30)))));
+        assert_eq!(hash.data.get("mixed"), Some(&Vector(vec!(Integer(1), Float(2.5), Strng("three".to_string()), Bool(true)))));
+    }
+
+    #[test]
+    fn test_from_json_str() {
+        let hash = HashBuilder::from_json_str(r#"{
+            "name": "Anduin",
+            "age": 21,
+            "weight": 120.5,
+            "king": false,
+            "title": null,
+            "cards": ["Prophet Velen", 7, {"name": "Hearthstone"}],
+            "home": {"city": "Stormwind", "zones": [1, 2]}
+        }"#).unwrap();
+
+        let mut card = OrderedMap::new();
+        card.insert("name".to_string(), Strng("Hearthstone".to_string()));
+        let mut home = OrderedMap::new();
+        home.insert("city".to_string(), Strng("Stormwind".to_string()));
+        home.insert("zones".to_string(), Vector(vec!(Integer(1), Integer(2))));
+
+        assert_eq!(hash.data.get("name"), Some(&Strng("Anduin".to_string())));
+        assert_eq!(hash.data.get("age"), Some(&Integer(21)));
+        assert_eq!(hash.data.get("weight"), Some(&Float(120.5)));
+        assert_eq!(hash.data.get("king"), Some(&Bool(false)));
+        assert_eq!(hash.data.get("title"), Some(&Null));
+        assert_eq!(hash.data.get("cards"), Some(&Vector(vec!(Strng("Prophet Velen".to_string()), Integer(7), Hash(card)))));
+        assert_eq!(hash.data.get("home"), Some(&Hash(home)));
+    }
+
+    #[test]
+    fn test_data_from_json() {
+        let mut object = BTreeMap::new();
+        object.insert("name".to_string(), Json::String("Anduin".to_string()));
+        let mut hash = OrderedMap::new();
+        hash.insert("name".to_string(), Strng("Anduin".to_string()));
+
+        assert_eq!(Data::from(Json::Object(object)), Hash(hash));
+        assert_eq!(Data::from(Json::Array(vec!(Json::I64(1), Json::Boolean(true)))), Vector(vec!(Integer(1), Bool(true))));
+        assert_eq!(Data::from(Json::I64(-21)), Integer(-21));
+        assert_eq!(Data::from(Json::I64(1 << 40)), Float((1i64 << 40) as f64));
+        assert_eq!(Data::from(Json::U64(21)), Integer(21));
+        assert_eq!(Data::from(Json::F64(120.5)), Float(120.5));
+        assert_eq!(Data::from(Json::String("Mage".to_string())), Strng("Mage".to_string()));
+        assert_eq!(Data::from(Json::Boolean(false)), Bool(false));
+        assert_eq!(Data::from(Json::Null), Null);
+    }
+
+    #[test]
+    fn test_from_json_str_errors() {
+        match HashBuilder::from_json_str("{\"name\": ") {
+            Err(JsonError(_)) => { },
+            other => panic!("expected a JsonError, got {:?}", other)
+        }
+        match HashBuilder::from_json_str("[1, 2]") {
+            Err(JsonError(_)) => { },
+            other => panic!("expected a JsonError, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn test_null_builders() {
+        let mut hash = OrderedMap::new();
+        hash.insert("nickname".to_string(), Null);
+        hash.insert("aliases".to_string(), Vector(vec!(Null, Strng("Varian".to_string()))));
+
+        let builder = HashBuilder::new()
+            .insert_null("nickname")
+            .insert_vector("aliases", |v| { v.push_null().push_string("Varian") });
+
+        assert_eq!(Hash(hash), builder.build());
+    }
+
+    #[test]
+    fn test_insert_option() {
+        let none: Option<String> = None;
+        let hash = HashBuilder::new()
+            .insert_option("name", Some("Anduin"))
+            .insert_option("title", none)
+            .insert_option("age", Some(21))
+            .insert_option("weight", None::<f64>)
+            .insert_option("king", Some(false));
+
+        assert_eq!(hash.data.get("name"), Some(&Strng("Anduin".to_string())));
+        assert_eq!(hash.data.get("title"), Some(&Null));
+        assert_eq!(hash.data.get("age"), Some(&Integer(21)));
+        assert_eq!(hash.data.get("weight"), Some(&Null));
+        assert_eq!(hash.data.get("king"), Some(&Bool(false)));
+    }
+
+    #[test]
+    fn test_get_and_contains_key() {
+        let hash = HashBuilder::new()
+            .insert_string("name", "Anduin")
+            .insert_int("age", 21)
+            .insert_bool("king", true)
+            .insert_vector("titles", |v| { v.push_string("Prince") })
+            .insert_hash("home", |h| { h.insert_string("city", "Stormwind") });
+
+        assert_eq!(hash.get("name"), Some(&Strng("Anduin".to_string())));
+        assert_eq!(hash.get("age"), Some(&Integer(21)));
+        assert_eq!(hash.get("king"), Some(&Bool(true)));
+        assert_eq!(hash.get("titles"), Some(&Vector(vec!(Strng("Prince".to_string())))));
+        match hash.get("home") {
+            Some(&Hash(ref home)) => assert_eq!(home.get("city"), Some(&Strng("Stormwind".to_string()))),
+            other => panic!("expected a hash, got {:?}", other)
+        }
+        assert_eq!(hash.get("missing"), None);
+        // dotted names aren't looked through
+        assert_eq!(hash.get("home.city"), None);
+
+        assert!(hash.contains_key("name"));
+        assert!(hash.contains_key("home"));
+        assert!(!hash.contains_key("missing"));
+    }
+
+    #[test]
+    fn test_insert_and_push_data() {
+        let classes = Vector(vec!(Strng("Mage".to_string()), Strng("Priest".to_string())));
+        let hash = HashBuilder::new()
+            .insert("name", Strng("Jaina".to_string()))
+            .insert("classes", classes)
+            .insert_vector("levels", |v| { v.push(Integer(60)).push(Null).push_int(70) });
+
+        assert_eq!(hash.get("name"), Some(&Strng("Jaina".to_string())));
+        assert_eq!(hash.get("levels"), Some(&Vector(vec!(Integer(60), Null, Integer(70)))));
+        assert_eq!(hash.keys(), vec!["name", "classes", "levels"]);
+        assert_eq!("Jaina: <Mage><Priest>".to_string(),
+                   render_string_from_hash("{{name}}: {{#classes}}<{{.}}>{{/classes}}", &hash).unwrap());
+    }
+
+    #[test]
+    fn test_data_display() {
+        let mut f = |s: String| s;
+        let lambda = Lambda(RefCell::new(&mut f));
+        let mut hash = OrderedMap::new();
+        hash.insert("name".to_string(), Strng("Anduin".to_string()));
+        hash.insert("level".to_string(), Integer(60));
+
+        assert_eq!("Anduin", format!("{}", Strng("Anduin".to_string())));
+        assert_eq!("true", format!("{}", Bool(true)));
+        assert_eq!("5", format!("{}", Integer(5)));
+        assert_eq!("1.5", format!("{}", Float(1.5)));
+        assert_eq!("", format!("{}", Null));
+        assert_eq!("<lambda>", format!("{}", lambda));
+        assert_eq!("a, 1, false", format!("{}", Vector(vec!(Strng("a".to_string()), Integer(1), Bool(false)))));
+        assert_eq!("", format!("{}", Vector(vec!())));
+        assert_eq!("name=Anduin, level=60", format!("{}", Hash(hash)));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_insert_now() {
+        let hash = HashBuilder::new()
+            .insert_now("year", "%Y")
+            .insert_now("stamp", "");
+
+        match hash.data.get("year") {
+            Some(&Strng(ref year)) => {
+                assert_eq!(year.len(), 4);
+                assert!(year.chars().all(|c| c.is_digit(10)));
+            },
+            _ => panic!("expected a string for year")
+        }
+        match hash.data.get("stamp") {
+            Some(&Strng(ref stamp)) => assert_eq!(stamp.len(), "2015-01-01 00:00:00".len()),
+            _ => panic!("expected a string for stamp")
+        }
+    }
+
+    #[test]
+    fn test_insertion_order() {
+        let hash = HashBuilder::new()
+            .insert_string("zeta", "z")
+            .insert_int("alpha", 1)
+            .insert_hash("mid", |h| { h })
+            .insert_bool("beta", true)
+            .insert_string("alpha", "replaced");
+
+        assert_eq!(hash.keys(), vec!["zeta", "alpha", "mid", "beta"]);
+        assert_eq!(hash.data.get("alpha"), Some(&Strng("replaced".to_string())));
+    }
+
+    #[test]
+    fn test_merge() {
+        let site = HashBuilder::new()
+            .set_partials_path("/site")
+            .insert_string("site", "Hearthstone")
+            .insert_string("title", "Home")
+            .insert_int("year", 2014);
+        let page = HashBuilder::new()
+            .set_partials_path("/page")
+            .insert_string("title", "Heroes")
+            .insert_hash("hero", |h| { h.insert_string("name", "Anduin") });
+
+        let data = site.merge(page);
+
+        assert_eq!(data.data.get("title"), Some(&Strng("Heroes".to_string())));
+        assert_eq!(data.data.get("site"), Some(&Strng("Hearthstone".to_string())));
+        assert_eq!(data.data.get("year"), Some(&Integer(2014)));
+        assert!(data.data.contains_key("hero"));
+        assert_eq!(data.keys(), vec!["site", "title", "year", "hero"]);
+        assert_eq!(data.partials_path, "/site");
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut f = |_| { "world".to_string() };
+        let mut g = |_| { "there".to_string() };
+        let before = HashBuilder::new()
+            .insert_string("name", "Anduin")
+            .insert_int("age", 21i32)
+            .insert_vector("cards", |v| { v.push_string("Prophet Velen") })
+            .insert_lambda("greet", &mut f)
+            .insert_bool("died", false);
+        let after = HashBuilder::new()
+            .insert_string("name", "Anduin")
+            .insert_int("age", 22i32)
+            .insert_vector("cards", |v| { v.push_string("Prophet Velen") })
+            .insert_lambda("greet", &mut g)
+            .insert_string("class", "Priest");
+
+        assert_eq!(before.diff(&after), vec![
+            KeyDiff::Changed("age".to_string()),
+            KeyDiff::Added("class".to_string()),
+            KeyDiff::Removed("died".to_string())]);
+        assert_eq!(after.diff(&after), vec![]);
+    }
+
     // #[test]
     // fn test_hash_lambda_builder() {
     //     // Since we can't directly compare closures, just make
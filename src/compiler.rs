@@ -1,5 +1,7 @@
 extern crate regex;
 
+use std::cmp::{min, max};
+
 use self::regex::Regex;
 use self::Token::*;
 
@@ -17,10 +19,24 @@ pub enum Token<'a> {
     OTag(&'a str, bool, &'a str), // (name, inverted, tag, whitespace)
     CTag(&'a str, &'a str), // (name, tag, whitespace)
     Raw(&'a str, &'a str), // (name, tag)
-    Partial(&'a str, &'a str), // (name, tag)
+    Partial(&'a str, &'a str, &'a str), // (name and arguments, tag, indentation)
+    Else(&'a str, &'a str), // (name, tag)
     Comment
 }
 
+// Arguments passed to a partial, e.g. the key1=a key2="b" in
+// {{> partial key1=a key2="b" }}.  Quoted values are string literals,
+// anything else is the name of a key in the data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum PartialArg<'a> {
+    Key(&'a str),
+    Literal(&'a str)
+}
+
+// Name of the section a {{!-- dev-only --}}...{{!--/dev-only--}} region
+// compiles to.  Comment tags can't be data keys, so it can't clash.
+pub const DEV_ONLY: &'static str = "!dev-only";
+
 // Entry point to the template compiler. It compiles a token list of
 // all applicable tags within a template to send to the parser.
 pub fn create_tokens<'a>(contents: &'a str) -> Vec<Token<'a>> {
@@ -31,11 +47,16 @@ pub fn create_tokens<'a>(contents: &'a str) -> Vec<Token<'a>> {
     let mut close_pos = 0;
     let len = contents.len();
 
-    // (text)(whitespace)( (tag) )(whitespace)
-    let re = Regex::new(r"(?s)(.*?)([ \t\r\n]*)(\{\{(\S?\s*?[\w\.\s]*.*?\s*?)\}\})([ \t\r\n]*)").unwrap();
+    let mut re = tag_regex("{{", "}}");
+
+    // Grab captures one at a time from the last closing position, since a
+    // set delimiter tag changes what the tags after it look like
+    loop {
+        let cap = match re.captures(&contents[close_pos..]) {
+            Some(cap) => cap,
+            None => break
+        };
 
-    // Grab all captures and process
-    for cap in re.captures_iter(contents) {
         // Establish groups for tag capture, preventing lookup for each call
         let preceding_text = cap.at(1).unwrap_or("");
         let preceding_whitespace = cap.at(2).unwrap_or("");
@@ -56,9 +77,17 @@ pub fn create_tokens<'a>(contents: &'a str) -> Vec<Token<'a>> {
             tokens.push(Text(preceding_whitespace));
         }
 
-        // Advance last closing position and add captured token
-        close_pos = c;
-        add_token(inner, outer, &mut tokens);
+        // Advance last closing position and add captured token.  Set
+        // delimiter tags switch the delimiters and, like comments, leave a
+        // Comment token that renders nothing but can stand alone on a line.
+        close_pos += c;
+        match set_delimiters(inner) {
+            Some((open, close)) => {
+                re = tag_regex(open, close);
+                tokens.push(Comment);
+            },
+            None => add_token(inner, outer, &mut tokens)
+        }
 
         // Catch trailing whitespace
         if !trailing_whitespace.is_empty() {
@@ -72,27 +101,342 @@ pub fn create_tokens<'a>(contents: &'a str) -> Vec<Token<'a>> {
     }
 
     // Return
-    tokens
+    trim_standalone(tokens)
+}
+
+// Section, inverted section, else, comment and partial tags that are the
+// only thing on their line are standalone, and the whole line is removed
+// from the output: the whitespace before the tag, and everything after it
+// up to and including the newline.  A standalone partial keeps the
+// whitespace before it as its indentation instead.
+fn trim_standalone<'a>(tokens: Vec<Token<'a>>) -> Vec<Token<'a>> {
+    // the part of each text token that is kept, as a start and end offset.
+    // lines are found on the untrimmed text, so that one text token can be
+    // trimmed from both ends by the tags either side of it.
+    let mut kept: Vec<(usize, usize)> = tokens.iter().map(|token| {
+        match *token {
+            Text(text) => (0, text.len()),
+            _ => (0, 0)
+        }
+    }).collect();
+    let mut indentation: Vec<&'a str> = tokens.iter().map(|_| "").collect();
+
+    for i in 0..tokens.len() {
+        match tokens[i] {
+            OTag(..) | CTag(..) | Else(..) | Partial(..) | Comment => {},
+            _ => continue
+        }
+        let (before, line_start) = match line_before(&tokens, i) {
+            Some(found) => found,
+            None => continue
+        };
+        let (after, line_end) = match line_after(&tokens, i) {
+            Some(found) => found,
+            None => continue
+        };
+
+        for j in before..i {
+            match tokens[j] {
+                Text(text) if j == before && line_start <= text.len() => {
+                    kept[j].1 = min(kept[j].1, line_start);
+                    match tokens[i] {
+                        Partial(..) if j + 1 == i => indentation[i] = &text[line_start..],
+                        _ => {}
+                    }
+                },
+                _ => kept[j].1 = 0
+            }
+        }
+        for j in i + 1 .. after {
+            kept[j].0 = kept[j].1;
+        }
+        if after < tokens.len() {
+            kept[after].0 = max(kept[after].0, line_end);
+        }
+    }
+
+    let mut rv = Vec::new();
+    for (i, token) in tokens.into_iter().enumerate() {
+        match token {
+            Text(text) => {
+                let (start, end) = kept[i];
+                if start < end {
+                    rv.push(Text(&text[start..end]));
+                }
+            },
+            Partial(name, raw, _) => rv.push(Partial(name, raw, indentation[i])),
+            other => rv.push(other)
+        }
+    }
+    rv
+}
+
+// Finds where the line holding the tag at i starts, as the index of the
+// text token holding the start of the line and the offset of the line in
+// it.  Gives None if anything but whitespace comes before the tag on its line.
+fn line_before<'a>(tokens: &Vec<Token<'a>>, i: usize) -> Option<(usize, usize)> {
+    let mut j = i;
+    while j > 0 {
+        j -= 1;
+        match tokens[j] {
+            Text(text) => {
+                match text.rfind('\n') {
+                    Some(pos) => {
+                        return match text[pos + 1..].trim().is_empty() {
+                            true => Some((j, pos + 1)),
+                            false => None
+                        };
+                    },
+                    None => {
+                        if !text.trim().is_empty() {
+                            return None;
+                        }
+                    }
+                }
+            },
+            _ => return None
+        }
+    }
+    // the tag is on the first line of the template
+    Some((0, 0))
+}
+
+// Finds where the line holding the tag at i ends, as the index of the text
+// token holding the newline and the offset just past it, or the end of the
+// tokens.  Gives None if anything but whitespace follows the tag on its line.
+fn line_after<'a>(tokens: &Vec<Token<'a>>, i: usize) -> Option<(usize, usize)> {
+    for j in i + 1 .. tokens.len() {
+        match tokens[j] {
+            Text(text) => {
+                match text.find('\n') {
+                    Some(pos) => {
+                        return match text[..pos].trim().is_empty() {
+                            true => Some((j, pos + 1)),
+                            false => None
+                        };
+                    },
+                    None => {
+                        if !text.trim().is_empty() {
+                            return None;
+                        }
+                    }
+                }
+            },
+            _ => return None
+        }
+    }
+    // the tag is on the last line of the template
+    Some((tokens.len(), 0))
+}
+
+// Builds the tag matching regex for the given delimiters
+// (text)(whitespace)( (tag) )(whitespace)
+// A tag starting with { runs to the matching }, so that the close of a
+// triple mustache isn't taken as the end of the tag.
+fn tag_regex(open: &str, close: &str) -> Regex {
+    let pattern = format!(r"(?s)(.*?)([ \t\r\n]*)({}(\{{.*?\}}|\S?\s*?[\w\.\s]*.*?\s*?){})([ \t\r\n]*)",
+                          regex::quote(open), regex::quote(close));
+    Regex::new(&pattern).unwrap()
+}
+
+// A set delimiter tag like {{=<% %>=}} holds the new open and close
+// delimiters between equals signs, separated by whitespace
+fn set_delimiters<'a>(inner: &'a str) -> Option<(&'a str, &'a str)> {
+    if inner.len() < 2 || !inner.starts_with("=") || !inner.ends_with("=") {
+        return None;
+    }
+    let delimiters: Vec<&str> = inner[1 .. inner.len() - 1].split_whitespace().collect();
+    match delimiters.len() {
+        2 => Some((delimiters[0], delimiters[1])),
+        _ => None
+    }
 }
 
 // Simple method for categorizing and adding appropriate token
 fn add_token<'a>(inner: &'a str, outer: &'a str, tokens: &mut Vec<Token<'a>>) {
     match &inner[0..1] {
-        "!" => tokens.push(Comment),
+        "!" => {
+            match dev_only_marker(&inner[1..]) {
+                Some(false) => tokens.push(OTag(DEV_ONLY, false, outer)),
+                Some(true) => tokens.push(CTag(DEV_ONLY, outer)),
+                None => tokens.push(Comment)
+            }
+        },
         "#" => tokens.push(OTag(inner[1..].trim(), false, outer)),
         "/" => tokens.push(CTag(inner[1..].trim(), outer)),
         "^" => tokens.push(OTag(inner[1..].trim(), true, outer)),
-        ">" => tokens.push(Partial(inner[1..].trim(), outer)),
+        ">" => tokens.push(Partial(inner[1..].trim(), outer, "")),
         "&" => tokens.push(Raw(inner[1..].trim(), outer)),
+        ":" => tokens.push(Else(inner[1..].trim(), outer)),
         "{" => tokens.push(Raw(inner[1 .. inner.len() - 1].trim(), outer)),
         _   => tokens.push(Variable(inner.trim(), outer))
     }
 }
 
+// Dev-only region markers are the comments -- dev-only -- and --/dev-only--,
+// giving whether the marker closes the region
+fn dev_only_marker(comment: &str) -> Option<bool> {
+    let comment = comment.trim();
+    if comment.len() < 4 || !comment.starts_with("--") || !comment.ends_with("--") {
+        return None;
+    }
+    match comment[2 .. comment.len() - 2].trim() {
+        "dev-only" => Some(false),
+        "/dev-only" => Some(true),
+        _ => None
+    }
+}
+
+// The partial name is the first word in the tag, anything after it
+// is arguments for the partial
+pub fn partial_name<'a>(contents: &'a str) -> &'a str {
+    let contents = contents.trim();
+    match contents.find(char::is_whitespace) {
+        Some(end) => &contents[..end],
+        None => contents
+    }
+}
+
+// The key a {{#set name}} section assigns to, if it is one, given the
+// section name
+pub fn set_target<'a>(name: &'a str) -> Option<&'a str> {
+    let mut words = name.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("set"), Some(name), None) => Some(name),
+        _ => None
+    }
+}
+
+// The number of times a {{#repeat 3}} section renders its body, if it
+// is one, given the section name
+pub fn repeat_count(name: &str) -> Option<usize> {
+    let mut words = name.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("repeat"), Some(count), None) => count.parse().ok(),
+        _ => None
+    }
+}
+
+// The hash a {{#each name}} section goes through, if it is one, given
+// the section name
+pub fn each_target<'a>(name: &'a str) -> Option<&'a str> {
+    let mut words = name.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (Some("each"), Some(name), None) => Some(name),
+        _ => None
+    }
+}
+
+// The name of the tag closing a section.  {{#set name}}, {{#repeat 3}} and
+// {{#each name}} sections are closed by a plain {{/set}}, {{/repeat}} and
+// {{/each}}, any other section by its own name.
+pub fn closing_name<'a>(name: &'a str) -> &'a str {
+    if set_target(name).is_some() {
+        "set"
+    } else if repeat_count(name).is_some() {
+        "repeat"
+    } else if each_target(name).is_some() {
+        "each"
+    } else {
+        name
+    }
+}
+
+// Parses the named arguments out of a partial tag, given the text inside
+// it, in the order they were given.  Pairs that are missing an = are ignored.
+pub fn partial_args<'a>(contents: &'a str) -> Vec<(&'a str, PartialArg<'a>)> {
+    let mut args = Vec::new();
+    let contents = contents.trim();
+    let mut rest = contents[partial_name(contents).len()..].trim();
+
+    while !rest.is_empty() {
+        let eq = match rest.find('=') {
+            Some(eq) => eq,
+            None => break
+        };
+        let key = rest[..eq].trim();
+        rest = rest[eq + 1..].trim();
+
+        let quote = match rest.chars().next() {
+            Some(c) if c == '"' || c == '\'' => Some(c),
+            _ => None
+        };
+        let (value, remaining) = match quote {
+            Some(q) => {
+                match rest[1..].find(q) {
+                    Some(end) => (PartialArg::Literal(&rest[1..end + 1]), &rest[end + 2..]),
+                    None => (PartialArg::Literal(&rest[1..]), "")
+                }
+            },
+            None => {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                (PartialArg::Key(&rest[..end]), &rest[end..])
+            }
+        };
+
+        if !key.is_empty() {
+            args.push((key, value));
+        }
+        rest = remaining.trim();
+    }
+
+    args
+}
+
+/// `TemplateNames` lists the names a template refers to, each once and in
+/// the order they first appear.  Dotted names are kept whole.
+#[derive(Debug, PartialEq)]
+pub struct TemplateNames {
+    /// Names of value tags, including keys passed as partial arguments
+    pub values: Vec<String>,
+    /// Names of sections and inverted sections
+    pub sections: Vec<String>,
+    /// Names of included partials
+    pub partials: Vec<String>
+}
+
+// Collects the names referred to by a list of tokens.  The implicit
+// iterator, loop variables like {{@index}}, and the built in set, repeat
+// and dev-only sections aren't names in the data, so they are left out.
+// The hash an each section goes through is listed as a section.
+pub fn template_names<'a>(tokens: &Vec<Token<'a>>) -> TemplateNames {
+    let mut names = TemplateNames { values: Vec::new(), sections: Vec::new(), partials: Vec::new() };
+
+    for token in tokens.iter() {
+        match *token {
+            Variable(name, _) | Raw(name, _) => add_name(&mut names.values, name),
+            OTag(DEV_ONLY, _, _) => {},
+            OTag(name, _, _) if each_target(name).is_some() => add_name(&mut names.sections, each_target(name).unwrap()),
+            OTag(name, _, _) if closing_name(name) != name => {},
+            OTag(name, _, _) => add_name(&mut names.sections, name),
+            Partial(contents, _, _) => {
+                add_name(&mut names.partials, partial_name(contents));
+                for (_, arg) in partial_args(contents).into_iter() {
+                    match arg {
+                        PartialArg::Key(key) => add_name(&mut names.values, key),
+                        PartialArg::Literal(_) => {}
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    names
+}
+
+fn add_name(names: &mut Vec<String>, name: &str) {
+    if name == "." || name.starts_with("@") || names.iter().any(|seen| seen == name) {
+        return;
+    }
+    names.push(name.to_string());
+}
+
 #[cfg(test)]
 mod compiler_tests {
     use compiler;
-    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Comment};
+    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Else, Comment};
+    use compiler::PartialArg;
 
     #[test]
     fn test_one_char() {
@@ -103,6 +447,70 @@ mod compiler_tests {
         assert_eq!(expected, tokens);
     }
 
+    #[test]
+    fn test_tag_name_whitespace() {
+        let contents = "{{name}}{{ name }}{{  name  }}{{&\tname\n}}{{#  list }}{{/list  }}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Variable("name", "{{name}}"),
+                            Variable("name", "{{ name }}"),
+                            Variable("name", "{{  name  }}"),
+                            Raw("name", "{{&\tname\n}}"),
+                            OTag("list", false, "{{#  list }}"),
+                            CTag("list", "{{/list  }}")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_template_names() {
+        let contents = "{{title}}{{#users}}{{name}} {{&user.bio}}{{^admin}}{{title}}{{/admin}}{{#tags}}{{.}}{{@index}}{{/tags}}\
+                        {{> card who=name label=\"x\" }}{{/users}}{{#repeat 2}}{{/repeat}}{{#set heading}}{{/set}}{{> card }}\
+                        {{#each stats}}{{@key}}{{/each}}";
+        let names = compiler::template_names(&compiler::create_tokens(contents));
+
+        assert_eq!(vec!["title", "name", "user.bio"], names.values);
+        assert_eq!(vec!["users", "admin", "tags", "stats"], names.sections);
+        assert_eq!(vec!["card"], names.partials);
+
+        let contents = "{{=<% %>=}}<%#set heading%><%title%><%/set%><%> card who=name %>";
+        let names = compiler::template_names(&compiler::create_tokens(contents));
+
+        assert_eq!(vec!["title", "name"], names.values);
+        assert_eq!(Vec::<String>::new(), names.sections);
+        assert_eq!(vec!["card"], names.partials);
+    }
+
+    #[test]
+    fn test_standalone_crlf() {
+        let contents = "a\r\n  {{#s}}\r\nb\r\n{{/s}}  \r\nc";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Text("a"),
+                            Text("\r\n"),
+                            OTag("s", false, "{{#s}}"),
+                            Text("b"),
+                            Text("\r\n"),
+                            CTag("s", "{{/s}}"),
+                            Text("c")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_triple_mustache() {
+        let contents = "{{{name}}}|{{{ name }}}|{{&name}}|{{=<% %>=}}<%{name}%>";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Raw("name", "{{{name}}}"),
+                            Text("|"),
+                            Raw("name", "{{{ name }}}"),
+                            Text("|"),
+                            Raw("name", "{{&name}}"),
+                            Text("|"),
+                            Comment,
+                            Raw("name", "<%{name}%>")];
+
+        assert_eq!(expected, tokens);
+    }
+
     #[test]
     fn test_extended_dot_notation() {
         let contents = "{{ test.test.test.test }}";
@@ -122,7 +530,7 @@ mod compiler_tests {
                             Text(" "),
                             Raw("unescaped", "{{{ unescaped }}}"),
                             Text(" "),
-                            Partial("partial", "{{> partial }}"),
+                            Partial("partial", "{{> partial }}", ""),
                             Text(" "),
                             Text("</h1> </div>")
                             ];
@@ -139,13 +547,25 @@ mod compiler_tests {
                             CTag("section", "{{/section}}"),
                             OTag("isection", true, "{{^isection}}"),
                             CTag("isection", "{{/isection}}"),
-                            Partial("partial", "{{>partial}}"),
+                            Partial("partial", "{{>partial}}", ""),
                             Raw("unescaped", "{{&unescaped}}"),
                             Variable("value", "{{value}}"),
                             Text("other crap")];
         assert_eq!(expected, tokens);
     }
 
+    #[test]
+    fn test_else_clause() {
+        let contents = "{{#section}}a{{:empty}}b{{/section}}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![OTag("section", false, "{{#section}}"),
+                            Text("a"),
+                            Else("empty", "{{:empty}}"),
+                            Text("b"),
+                            CTag("section", "{{/section}}")];
+        assert_eq!(expected, tokens);
+    }
+
     #[test]
     fn test_missing_close_on_comment() {
         let contents = "{{!comment";
@@ -211,7 +631,7 @@ mod compiler_tests {
     fn test_working_partial() {
         let contents = "{{>partial}}";
         let tokens = compiler::create_tokens(contents);
-        let expected = vec![Partial("partial", "{{>partial}}")];
+        let expected = vec![Partial("partial", "{{>partial}}", "")];
         assert_eq!(expected, tokens);
     }
 
@@ -235,7 +655,7 @@ mod compiler_tests {
     fn test_missing_close_on_partial_plus_unescaped() {
         let contents = "{{>partial}}{{&unescaped";
         let tokens = compiler::create_tokens(contents);
-        let expected = vec![Partial("partial", "{{>partial}}"), Text("{{&unescaped")];
+        let expected = vec![Partial("partial", "{{>partial}}", ""), Text("{{&unescaped")];
         assert_eq!(expected, tokens);
     }
 
@@ -263,6 +683,111 @@ mod compiler_tests {
         assert_eq!(expected, tokens);
     }
 
+    #[test]
+    fn test_set_delimiters() {
+        let contents = "{{=<% %>=}}<% name %> {{ literal }}<%={{ }}=%>{{ name }}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Comment, Variable("name", "<% name %>"), Text(" "),
+                            Text("{{ literal }}"), Comment, Variable("name", "{{ name }}")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_set_delimiters_no_output() {
+        let contents = "a{{= | | =}}b|# section |c|/ section |";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Text("a"), Comment, Text("b"), OTag("section", false, "|# section |"),
+                            Text("c"), CTag("section", "|/ section |")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_set_delimiters_standalone() {
+        let contents = "Begin.\n{{=@ @=}}\nEnd.";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Text("Begin."), Text("\n"), Comment, Text("End.")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_partial_with_args() {
+        let contents = "{{> user name=author title=\"Dr. Who\" }}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Partial("user name=author title=\"Dr. Who\"", "{{> user name=author title=\"Dr. Who\" }}", "")];
+        assert_eq!(expected, tokens);
+
+        let args = compiler::partial_args("user name=author title=\"Dr. Who\"");
+        let expected = vec![("name", PartialArg::Key("author")), ("title", PartialArg::Literal("Dr. Who"))];
+        assert_eq!(expected, args);
+    }
+
+    #[test]
+    fn test_partial_without_args() {
+        assert_eq!(Vec::<(&str, PartialArg)>::new(), compiler::partial_args("user"));
+        assert_eq!(Vec::<(&str, PartialArg)>::new(), compiler::partial_args(" user "));
+    }
+
+    #[test]
+    fn test_dev_only_markers() {
+        let contents = "a{{!-- dev-only --}}b{{!--/dev-only--}}{{! note }}";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Text("a"), OTag(compiler::DEV_ONLY, false, "{{!-- dev-only --}}"),
+                            Text("b"), CTag(compiler::DEV_ONLY, "{{!--/dev-only--}}"), Comment];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_set_tag() {
+        let tokens = compiler::create_tokens("{{#set title}}x{{/set}}{{#set}}y{{/set}}");
+        let expected = vec![OTag("set title", false, "{{#set title}}"), Text("x"), CTag("set", "{{/set}}"),
+                            OTag("set", false, "{{#set}}"), Text("y"), CTag("set", "{{/set}}")];
+
+        assert_eq!(expected, tokens);
+        assert_eq!(Some("title"), compiler::set_target("set title"));
+        assert_eq!(None, compiler::set_target("set"));
+        assert_eq!("set", compiler::closing_name("set title"));
+    }
+
+    #[test]
+    fn test_repeat_tag() {
+        let tokens = compiler::create_tokens("{{#repeat 3}}x{{/repeat}}{{#repeat many}}y{{/repeat}}");
+        let expected = vec![OTag("repeat 3", false, "{{#repeat 3}}"), Text("x"), CTag("repeat", "{{/repeat}}"),
+                            OTag("repeat many", false, "{{#repeat many}}"), Text("y"), CTag("repeat", "{{/repeat}}")];
+
+        assert_eq!(expected, tokens);
+        assert_eq!(Some(3), compiler::repeat_count("repeat 3"));
+        assert_eq!(None, compiler::repeat_count("repeat many"));
+        assert_eq!("repeat many", compiler::closing_name("repeat many"));
+    }
+
+    #[test]
+    fn test_standalone_partial_indentation() {
+        let contents = "a\n    {{> new }}\nb {{> new }}\n";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Text("a"), Text("\n"), Partial("new", "{{> new }}", "    "),
+                            Text("b"), Text(" "), Partial("new", "{{> new }}", ""), Text("\n")];
+
+        assert_eq!(expected, tokens);
+    }
+
+    #[test]
+    fn test_standalone_lines_removed() {
+        let contents = "<ul>\n  {{#items}}\n  <li>{{name}}</li>\n  {{/items}}\n</ul>\n{{#a}}{{/a}}\n";
+        let tokens = compiler::create_tokens(contents);
+        let expected = vec![Text("<ul>"), Text("\n"),
+                            OTag("items", false, "{{#items}}"),
+                            Text("  "), Text("<li>"), Variable("name", "{{name}}"), Text("</li>"), Text("\n"),
+                            CTag("items", "{{/items}}"),
+                            Text("</ul>"), Text("\n"),
+                            OTag("a", false, "{{#a}}"), CTag("a", "{{/a}}"), Text("\n")];
+
+        assert_eq!(expected, tokens);
+    }
+
     #[test]
     fn test_single_brace_close() {
         let contents = "value} other crap";
@@ -6,16 +6,30 @@
 //! Rustache is a flexible template engine for Rust.
 
 extern crate rustc_serialize;
+#[cfg(feature = "time")]
+extern crate time;
+#[cfg(feature = "serde")]
+#[cfg_attr(test, macro_use)]
+extern crate serde_json;
+#[cfg(feature = "yaml")]
+extern crate yaml_rust;
 
 use std::fmt;
 use std::cell::RefCell;
-use std::collections::HashMap;
 
 use self::RustacheError::*;
 use self::Data::*;
 
-pub use build::{HashBuilder, VecBuilder};
-pub use rustache::{render_file, render_text, Render};
+pub use build::{HashBuilder, VecBuilder, KeyDiff};
+pub use partials::PartialCache;
+pub use map::OrderedMap;
+pub use template::{EscapeMode, NullMode, FinalNewline, TagKind, set_default_escape_mode, default_escape_mode, default_truthiness};
+pub use compiler::TemplateNames;
+pub use rustache::{render_file, render_text, render_fragments, render_cow, render_namespaced, render_rows, render_collect_errors, render_string_from_hash, render_to_string, render_file_to_string, render_text_with_partials, render_with_context, render_to_buf, template_names, CompiledTemplate, Render};
+#[cfg(feature = "serde")]
+pub use rustache::render_serde;
+#[cfg(feature = "yaml")]
+pub use rustache::render_file_with_front_matter;
 
 /// Alias for Result<T, RustacheError>
 pub type RustacheResult<T> = Result<T, RustacheError>;
@@ -50,8 +64,10 @@ pub enum Data<'a> {
     Integer(i32),
     Float(f64),
     Vector(Vec<Data<'a>>),
-    Hash(HashMap<String, Data<'a>>),
-    Lambda(RefCell<&'a mut FnMut(String) -> String>)
+    Hash(OrderedMap<Data<'a>>),
+    Lambda(RefCell<&'a mut FnMut(String) -> String>),
+    DataLambda(RefCell<&'a mut FnMut(String) -> Data<'a>>),
+    Null
 }
 // |String|: 'a -> String : F Above
 
@@ -66,6 +82,8 @@ impl<'a> PartialEq for Data<'a> {
             (&Vector(ref val0), &Vector(ref val1)) => val0 == val1,
             (&Hash(ref val0), &Hash(ref val1)) => val0 == val1,
             (&Lambda(_), &Lambda(_)) => panic!("Can't compare closures"),
+            (&DataLambda(_), &DataLambda(_)) => panic!("Can't compare closures"),
+            (&Null, &Null) => true,
             (_, _) => false
         }
     }
@@ -81,14 +99,84 @@ impl<'a> fmt::Debug for Data<'a> {
             Float(ref val)   => write!(f, "Float({:?})", val),
             Vector(ref val)  => write!(f, "Vector({:?})", val),
             Hash(ref val)    => write!(f, "Hash({:?})", val),
-            Lambda(_)        => write!(f, "Lambda(...)")
+            Lambda(_)        => write!(f, "Lambda(...)"),
+            DataLambda(_)    => write!(f, "DataLambda(...)"),
+            Null             => write!(f, "Null")
         }
     }
 }
 
+// Prints the plain value, vectors joined with commas and hashes as
+// key=value pairs in insertion order
+impl<'a> fmt::Display for Data<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Strng(ref val)   => write!(f, "{}", val),
+            Bool(val)        => write!(f, "{}", val),
+            Integer(val)     => write!(f, "{}", val),
+            Float(val)       => write!(f, "{}", val),
+            Vector(ref val)  => {
+                for (i, item) in val.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(f, ", "));
+                    }
+                    try!(write!(f, "{}", item));
+                }
+                Ok(())
+            },
+            Hash(ref val)    => {
+                for (i, (key, item)) in val.iter().enumerate() {
+                    if i > 0 {
+                        try!(write!(f, ", "));
+                    }
+                    try!(write!(f, "{}={}", key, item));
+                }
+                Ok(())
+            },
+            Lambda(_)        => write!(f, "<lambda>"),
+            DataLambda(_)    => write!(f, "<lambda>"),
+            Null             => Ok(())
+        }
+    }
+}
+
+// Conversions from plain values, so builders can take anything that
+// turns into Data
+impl<'a> From<String> for Data<'a> {
+    fn from(val: String) -> Data<'a> {
+        Strng(val)
+    }
+}
+
+impl<'a, 'b> From<&'b str> for Data<'a> {
+    fn from(val: &'b str) -> Data<'a> {
+        Strng(val.to_string())
+    }
+}
+
+impl<'a> From<bool> for Data<'a> {
+    fn from(val: bool) -> Data<'a> {
+        Bool(val)
+    }
+}
+
+impl<'a> From<i32> for Data<'a> {
+    fn from(val: i32) -> Data<'a> {
+        Integer(val)
+    }
+}
+
+impl<'a> From<f64> for Data<'a> {
+    fn from(val: f64) -> Data<'a> {
+        Float(val)
+    }
+}
+
 // Internal Modules
 mod rustache;
 mod compiler;
 mod parser;
 mod build;
 mod template;
+mod partials;
+mod map;
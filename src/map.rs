@@ -0,0 +1,207 @@
+use std::fmt;
+use std::slice;
+use std::vec;
+use std::iter::FromIterator;
+use std::collections::HashMap;
+
+/// `OrderedMap` holds the entries of a hash in the order their keys were
+/// first inserted, with an index for looking keys up.  Replacing a key keeps
+/// its original position.  Two maps are equal when they hold the same
+/// entries, whatever their order.
+#[derive(Clone)]
+pub struct OrderedMap<V> {
+    entries: Vec<(String, V)>,
+    index: HashMap<String, usize>
+}
+
+impl<V> OrderedMap<V> {
+    /// Create a new, empty `OrderedMap`
+    pub fn new() -> OrderedMap<V> {
+        OrderedMap {
+            entries: Vec::new(),
+            index: HashMap::new()
+        }
+    }
+
+    /// Store the value under the key, returning the value it replaced
+    pub fn insert(&mut self, key: String, value: V) -> Option<V> {
+        match self.index.get(&key) {
+            Some(&pos) => {
+                let old = ::std::mem::replace(&mut self.entries[pos].1, value);
+                return Some(old);
+            },
+            None => {}
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// Take the value stored under the key out of the map.  The entries
+    /// after it keep their order.
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let pos = match self.index.remove(key) {
+            Some(pos) => pos,
+            None => return None
+        };
+        let (_, value) = self.entries.remove(pos);
+        for &(ref key, _) in self.entries[pos..].iter() {
+            *self.index.get_mut(key).unwrap() -= 1;
+        }
+        Some(value)
+    }
+
+    /// Get the value stored under the key
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.index.get(key).map(|&pos| &self.entries[pos].1)
+    }
+
+    /// Get the value stored under the key, to change it
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        match self.index.get(key) {
+            Some(&pos) => Some(&mut self.entries[pos].1),
+            None => None
+        }
+    }
+
+    /// Whether anything is stored under the key
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entries, in insertion order
+    pub fn iter<'m>(&'m self) -> Iter<'m, V> {
+        Iter { inner: self.entries.iter() }
+    }
+
+    /// The keys, in insertion order
+    pub fn keys<'m>(&'m self) -> Keys<'m, V> {
+        Keys { inner: self.entries.iter() }
+    }
+}
+
+impl<V> Default for OrderedMap<V> {
+    fn default() -> OrderedMap<V> {
+        OrderedMap::new()
+    }
+}
+
+impl<V: PartialEq> PartialEq for OrderedMap<V> {
+    fn eq(&self, other: &OrderedMap<V>) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<V: fmt::Debug> fmt::Debug for OrderedMap<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<V> Extend<(String, V)> for OrderedMap<V> {
+    fn extend<T: IntoIterator<Item = (String, V)>>(&mut self, iter: T) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<V> FromIterator<(String, V)> for OrderedMap<V> {
+    fn from_iter<T: IntoIterator<Item = (String, V)>>(iter: T) -> OrderedMap<V> {
+        let mut map = OrderedMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> IntoIterator for OrderedMap<V> {
+    type Item = (String, V);
+    type IntoIter = vec::IntoIter<(String, V)>;
+
+    fn into_iter(self) -> vec::IntoIter<(String, V)> {
+        self.entries.into_iter()
+    }
+}
+
+impl<'m, V> IntoIterator for &'m OrderedMap<V> {
+    type Item = (&'m String, &'m V);
+    type IntoIter = Iter<'m, V>;
+
+    fn into_iter(self) -> Iter<'m, V> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of an `OrderedMap`, in insertion order
+pub struct Iter<'m, V: 'm> {
+    inner: slice::Iter<'m, (String, V)>
+}
+
+impl<'m, V> Iterator for Iter<'m, V> {
+    type Item = (&'m String, &'m V);
+
+    fn next(&mut self) -> Option<(&'m String, &'m V)> {
+        self.inner.next().map(|&(ref key, ref value)| (key, value))
+    }
+}
+
+/// Iterator over the keys of an `OrderedMap`, in insertion order
+pub struct Keys<'m, V: 'm> {
+    inner: slice::Iter<'m, (String, V)>
+}
+
+impl<'m, V> Iterator for Keys<'m, V> {
+    type Item = &'m String;
+
+    fn next(&mut self) -> Option<&'m String> {
+        self.inner.next().map(|&(ref key, _)| key)
+    }
+}
+
+#[cfg(test)]
+mod map_tests {
+    use map::OrderedMap;
+
+    #[test]
+    fn test_insertion_order() {
+        let mut map = OrderedMap::new();
+        map.insert("zeta".to_string(), 1);
+        map.insert("alpha".to_string(), 2);
+        map.insert("mid".to_string(), 3);
+        assert_eq!(Some(1), map.insert("zeta".to_string(), 4));
+
+        let keys: Vec<&String> = map.keys().collect();
+        assert_eq!(vec!["zeta", "alpha", "mid"], keys);
+        assert_eq!(Some(&4), map.get("zeta"));
+    }
+
+    #[test]
+    fn test_remove_keeps_order() {
+        let mut map: OrderedMap<i32> = vec![("a".to_string(), 1), ("b".to_string(), 2), ("c".to_string(), 3)].into_iter().collect();
+
+        assert_eq!(Some(2), map.remove("b"));
+        assert_eq!(None, map.remove("b"));
+        assert_eq!(Some(&3), map.get("c"));
+
+        let entries: Vec<(String, i32)> = map.into_iter().collect();
+        assert_eq!(vec![("a".to_string(), 1), ("c".to_string(), 3)], entries);
+    }
+
+    #[test]
+    fn test_eq_ignores_order() {
+        let ab: OrderedMap<i32> = vec![("a".to_string(), 1), ("b".to_string(), 2)].into_iter().collect();
+        let ba: OrderedMap<i32> = vec![("b".to_string(), 2), ("a".to_string(), 1)].into_iter().collect();
+
+        assert_eq!(ab, ba);
+    }
+}
@@ -3,10 +3,10 @@
 // Nodes contain only the necessary information to be used
 // to seek out appropriate data for injection.
 
+use compiler;
 use compiler::Token;
-use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Comment};
+use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Else, Comment};
 use self::Node::*;
-use self::ParserStatus::*;
 
 // Node signifies the data structure used by the template to
 // determine how to correctly implement data.  Each Node type
@@ -19,29 +19,29 @@ pub enum Node<'a> {
     Value(&'a str, String), // (name, tag)
     Section(&'a str, Vec<Node<'a>>, bool, String, String), // (name, children, inverted, otag, ctag)
     Unescaped(&'a str, String), // (name, tag)
-    Part(&'a str, &'a str) // // (name, tag)
+    Part(&'a str, &'a str, &'a str) // (name and arguments, tag, indentation)
 }
 
-#[derive(PartialEq, Eq, Debug)]
-enum ParserStatus {
-    Parse,
-    // Sect,
-    Skip
-}
-
-trait LocalStringExt {
-    fn is_whitespace(&self) -> bool;
-}
-
-impl LocalStringExt for String {
-    fn is_whitespace(&self) -> bool {
-        self.chars().all(|c| c.is_whitespace())
-    }
-}
-
-impl LocalStringExt for str {
-    fn is_whitespace(&self) -> bool {
-        self.chars().all(|c| c.is_whitespace())
+impl<'a> Node<'a> {
+    // Compares two nodes by structure only, ignoring the raw tag text
+    // and delimiters, so {{ name }} and {{name}} are considered equal.
+    // Static text is still compared as-is.
+    pub fn structural_eq(&self, other: &Node) -> bool {
+        match (self, other) {
+            (&Static(a), &Static(b)) => a == b,
+            (&Value(a, _), &Value(b, _)) => a == b,
+            (&Unescaped(a, _), &Unescaped(b, _)) => a == b,
+            (&Part(a, _, a_indent), &Part(b, _, b_indent)) => {
+                compiler::partial_name(a) == compiler::partial_name(b) &&
+                    compiler::partial_args(a) == compiler::partial_args(b) && a_indent == b_indent
+            },
+            (&Section(a, ref a_children, a_inverted, _, _), &Section(b, ref b_children, b_inverted, _, _)) => {
+                a == b && a_inverted == b_inverted &&
+                    a_children.len() == b_children.len() &&
+                    a_children.iter().zip(b_children.iter()).all(|(x, y)| x.structural_eq(y))
+            },
+            _ => false
+        }
     }
 }
 
@@ -49,42 +49,69 @@ impl LocalStringExt for str {
 // Section nodes will be handled recursively
 pub fn parse_nodes<'a>(list: &Vec<Token<'a>>) -> Vec<Node<'a>> {
     let mut nodes: Vec<Node> = vec![];
-    let mut it = list.iter().enumerate().peekable();
-    let mut status = Parse;
+    let mut it = list.iter().enumerate();
 
     loop {
         // Iterate while still nodes in the list
         match it.next() {
             Some((i, token)) => {
                 match token {
-                    &Text(text) => nodes.push(parse_text_node(text, &mut status)),
+                    &Text(text) => nodes.push(Static(text)),
                     &Variable(name, raw) => nodes.push(parse_variable_node(name, raw)),
                     &Raw(name, raw) => nodes.push(parse_raw_node(name, raw)),
-                    &Partial(name, raw) => nodes.push(Part(name, raw)),
-                    // Unopened closing tags are ignored
-                    // TODO: Return a parser error?
+                    &Partial(name, raw, indentation) => nodes.push(Part(name, raw, indentation)),
+                    // Unopened closing tags are ignored here, parse_checked
+                    // rejects them along with mismatched ones
                     &CTag(_, _) => continue,
+                    // Else tags outside of a section have nothing to attach to
+                    &Else(_, _) => continue,
                     &OTag(name, inverted, raw) => {
+                        let close = compiler::closing_name(name);
                         let mut children: Vec<Token<'a>> = vec![];
                         let mut count = 0u32;
                         let mut otag_count = 1u32;
+                        // depth of any nested sections, so that only an else tag
+                        // directly inside this section splits its children
+                        let mut depth = 0u32;
+                        let mut else_branch: Option<(Vec<Token<'a>>, &'a str)> = None;
                         for item in list[i + 1 ..].iter() {
                             count += 1;
                             match *item {
                                 OTag(title, _, _) => {
-                                    if title == name {
+                                    if compiler::closing_name(title) == close {
                                         otag_count += 1;
                                     }
+                                    depth += 1;
                                     children.push((*item).clone());
                                 },
+                                Else(_, temp) if depth == 0 => {
+                                    else_branch = Some((children, temp));
+                                    children = vec![];
+                                },
                                 CTag(title, temp) => {
-                                    if title == name && otag_count == 1 {
-                                        nodes.push(Section(name, parse_nodes(&children).clone(), inverted, raw.to_string(), temp.to_string()));
+                                    if title == close && otag_count == 1 {
+                                        match else_branch {
+                                            // An else clause renders as an inverted section
+                                            // following the main one, so it sees the outer scope
+                                            Some((ref main, else_raw)) => {
+                                                nodes.push(Section(name, parse_nodes(main).clone(), inverted, raw.to_string(), else_raw.to_string()));
+                                                nodes.push(Section(name, parse_nodes(&children).clone(), !inverted, else_raw.to_string(), temp.to_string()));
+                                            },
+                                            None => {
+                                                nodes.push(Section(name, parse_nodes(&children).clone(), inverted, raw.to_string(), temp.to_string()));
+                                            }
+                                        }
                                         break;
-                                    } else if title == name && otag_count > 1 {
+                                    } else if title == close && otag_count > 1 {
                                         otag_count -= 1;
+                                        if depth > 0 {
+                                            depth -= 1;
+                                        }
                                         children.push((*item).clone());
                                     } else {
+                                        if depth > 0 {
+                                            depth -= 1;
+                                        }
                                         children.push((*item).clone());
                                         continue;
                                     }
@@ -97,36 +124,16 @@ pub fn parse_nodes<'a>(list: &Vec<Token<'a>>) -> Vec<Node<'a>> {
                         }
 
                         // Advance the iterator to the position of the CTAG.  If the
-                        // OTag is never closed, these children will never be processed.
-                        // TODO: Return a parser warning in the case of an unclosed tag?
+                        // OTag is never closed, these children will never be processed,
+                        // parse_checked reports that case as an error instead.
                         while count > 1 {
                             it.next();
                             count -= 1;
                         }
                     },
-                    &Comment => {
-                        // Check the next element for whitespace
-                        match it.peek() {
-                            Some(&(_, token)) => {
-                                match parse_comment_node(token, &mut status, &mut nodes) {
-                                    true => {
-                                        // it.next();
-                                    },
-                                    false => {}
-                                }
-                            },
-                            None => {
-                                match nodes.last().unwrap() {
-                                    &Static(text) => {
-                                        if text.is_whitespace() {
-                                            nodes.pop();
-                                        }
-                                    }
-                                    _ => continue,
-                                }
-                            },
-                        }
-                    },
+                    // Comments render nothing, and standalone comment lines
+                    // have already been removed by the compiler
+                    &Comment => continue,
                 }
             },
             None => break
@@ -137,23 +144,54 @@ pub fn parse_nodes<'a>(list: &Vec<Token<'a>>) -> Vec<Node<'a>> {
     nodes
 }
 
-// Helper function for handling the creation of a text node
-fn parse_text_node<'a>(text: &'a str, status: &mut ParserStatus) -> Node<'a> {
-    match *status {
-        _ => {
-            if text.contains("\n") {
-                *status = Skip;
-            } else if text.is_whitespace() {
-                *status = Skip;
-            }
-            return Static(text);
+// Checks that every section tag in the list is closed, in order, and that
+// no closing tag appears without its opening tag.  Gives a description of
+// the first problem found.
+pub fn check_sections<'a>(list: &Vec<Token<'a>>) -> Result<(), String> {
+    let mut open: Vec<&str> = Vec::new();
+    for token in list.iter() {
+        match *token {
+            OTag(name, _, _) => open.push(compiler::closing_name(name)),
+            CTag(name, _) => {
+                match open.pop() {
+                    Some(expected) if expected == name => {},
+                    Some(expected) => return Err(format!("section {} closed by {}", expected, name)),
+                    None => return Err(format!("section {} closed without being opened", name))
+                }
+            },
+            _ => {}
         }
     }
+
+    match open.pop() {
+        Some(name) => Err(format!("unclosed section {}", name)),
+        None => Ok(())
+    }
+}
+
+// Parses a list of tokens as parse_nodes does, but fails with a
+// description of the first unclosed or mismatched section rather than
+// quietly dropping its children
+pub fn parse_checked<'a>(list: &Vec<Token<'a>>) -> Result<Vec<Node<'a>>, String> {
+    try!(check_sections(list));
+    Ok(parse_nodes(list))
+}
+
+// Compiles template source straight into nodes, for callers that have
+// no use for the tokens in between
+pub fn compile<'a>(contents: &'a str) -> Vec<Node<'a>> {
+    parse_nodes(&compiler::create_tokens(contents))
+}
+
+// Compiles template source into nodes, failing as parse_checked does
+pub fn compile_checked<'a>(contents: &'a str) -> Result<Vec<Node<'a>>, String> {
+    parse_checked(&compiler::create_tokens(contents))
 }
 
 // Helper function for handling the creation of a variable node
 fn parse_variable_node<'a>(name: &'a str, raw: &'a str) -> Node<'a> {
-    let dot_notation = name.contains(".");
+    // a lone dot is the implicit iterator, not dot notation
+    let dot_notation = name.contains(".") && name != ".";
     match dot_notation {
         false => return Value(name, raw.to_string()),
         true => {
@@ -166,7 +204,7 @@ fn parse_variable_node<'a>(name: &'a str, raw: &'a str) -> Node<'a> {
 
 // Helper function for handling the creation of an unescaped variable node
 fn parse_raw_node<'a>(name: &'a str, raw: &'a str) -> Node<'a> {
-    let dot_notation = name.contains(".");
+    let dot_notation = name.contains(".") && name != ".";
     let ampersand = raw.contains("&");
     match dot_notation {
         false => {
@@ -188,92 +226,36 @@ fn parse_raw_node<'a>(name: &'a str, raw: &'a str) -> Node<'a> {
     }
 }
 
-// Helper function for handling the creation of comment nodes and
-// properly handle whitespace
-fn parse_comment_node<'a>(token: &Token, status: &mut ParserStatus, nodes: &mut Vec<Node<'a>>) -> bool {
-    match *token {
-        Text(ref value) => {
-            match *status {
-                Skip => {
-                    // If whitespace and should skip, advance to next token
-                    if value.is_whitespace() {
-                        match nodes.last().unwrap() {
-                            &Static(text) => {
-                                // If the previous node is whitespace and has a newline
-                                // then remove it
-                                if text.is_whitespace() && text.contains("\n") {
-                                    nodes.pop();
-                                }
-                            },
-                            _ => {}
-                        }
-                        *status = Parse;
-                        return true;
-                    } else {
-                        *status = Parse;
-                        return false;
-                    }
-                },
-                Parse => return false,
-                // Sect => return false,
-            }
-        },
-        _ => return false
-    }
+// Handle tag names that utilize dot notation shorthand.  {{ a.b.c }} is
+// parsed into nested sections around a value tag.  The outer section keeps
+// the whole tag as its text, and nothing inside has any text of its own, so
+// a section with no closing text is how the renderer tells dot notation
+// from sections written out in the template.
+fn handle_dot_notation<'a>(parts: &[&'a str], unescaped: bool, amp: bool) -> Node<'a> {
+    let name = parts.join(".");
+    let tag = match (unescaped, amp) {
+        (false, _) => format!("{{{{{}}}}}", name),
+        (true, true) => format!("{{{{&{}}}}}", name),
+        (true, false) => format!("{{{{{{{}}}}}}}", name)
+    };
+    dotted_node(parts, unescaped, tag)
 }
 
-// Recursively handle tag names that utilize dot notation shorthand
-fn handle_dot_notation<'a>(parts: &[&'a str], unescaped: bool, amp: bool) -> Node<'a> {
+// Recursively builds the nodes for the parts of a dotted name
+fn dotted_node<'a>(parts: &[&'a str], unescaped: bool, tag: String) -> Node<'a> {
     let variable = parts[0];
-    match parts.len() {
-        // Determine if the remaining portion of the tag name is the
-        // variable or another section.
-        1 => {
-            match unescaped {
-                // Determine if the matching tag is unesecaped or normal.
-                true => {
-                    match amp {
-                        true => {
-                            let mut var = "{{&".to_string();
-                            var.push_str(variable);
-                            var.push_str("}}");
-                            return Unescaped(variable, var);
-                        },
-                        false => {
-                            let mut var = "{{{".to_string();
-                            var.push_str(variable);
-                            var.push_str("}}}");
-                            return Unescaped(variable, var);
-                        }
-                    }
-                }
-                false => {
-                    let mut var = "{{".to_string();
-                    var.push_str(variable);
-                    var.push_str("}}");
-                    return Value(variable, var);
-                }
-            }
-        }
-        _ => {
-            let mut otag = "{{#".to_string();
-            let mut ctag = "{{/".to_string();
-
-            otag.push_str(variable);
-            otag.push_str("}}");
-            ctag.push_str(variable);
-            ctag.push_str("}}");
-
-            // Enter recursion and assign the results as children.
-            return Section(variable, vec![handle_dot_notation(&parts[1..], unescaped, amp)], false, otag, ctag);
-        }
+    match (parts.len(), unescaped) {
+        (1, true) => Unescaped(variable, tag),
+        (1, false) => Value(variable, tag),
+        _ => Section(variable, vec![dotted_node(&parts[1..], unescaped, String::new())], false, tag, String::new())
     }
 }
 
 #[cfg(test)]
 mod parser_tests {
+    use compiler;
     use compiler::Token;
-    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial};
+    use compiler::Token::{Text, Variable, OTag, CTag, Raw, Partial, Else};
     use parser;
     use parser::Node;
     use parser::Node::{Static, Value, Section, Unescaped, Part};
@@ -282,7 +264,15 @@ mod parser_tests {
     fn parse_dot_notation_simple() {
         let tokens: Vec<Token> = vec![Variable("section.child_tag", "{{ section.child_tag }}")];
         let nodes = parser::parse_nodes(&tokens);
-        let expected: Vec<Node> = vec![Section("section", vec![Value("child_tag", "{{child_tag}}".to_string())], false, "{{#section}}".to_string(), "{{/section}}".to_string())];
+        let expected: Vec<Node> = vec![Section("section", vec![Value("child_tag", "".to_string())], false, "{{section.child_tag}}".to_string(), "".to_string())];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn parse_implicit_iterator() {
+        let tokens: Vec<Token> = vec![Variable(".", "{{.}}"), Raw(".", "{{& . }}")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![Value(".", "{{.}}".to_string()), Unescaped(".", "{{& . }}".to_string())];
         assert_eq!(nodes, expected);
     }
 
@@ -290,7 +280,7 @@ mod parser_tests {
     fn parse_dot_notation_triple_mustache() {
         let tokens: Vec<Token> = vec![Raw("section.child_tag", "{{{ section.child_tag }}}")];
         let nodes = parser::parse_nodes(&tokens);
-        let expected: Vec<Node> = vec![Section("section", vec![Unescaped("child_tag", "{{{child_tag}}}".to_string())], false, "{{#section}}".to_string(), "{{/section}}".to_string())];
+        let expected: Vec<Node> = vec![Section("section", vec![Unescaped("child_tag", "".to_string())], false, "{{{section.child_tag}}}".to_string(), "".to_string())];
         assert_eq!(nodes, expected);
     }
 
@@ -298,7 +288,7 @@ mod parser_tests {
     fn parse_dot_notation_ampersand() {
         let tokens: Vec<Token> = vec![Raw("section.child_tag", "{{& section.child_tag }}")];
         let nodes = parser::parse_nodes(&tokens);
-        let expected: Vec<Node> = vec![Section("section", vec![Unescaped("child_tag", "{{&child_tag}}".to_string())], false, "{{#section}}".to_string(), "{{/section}}".to_string())];
+        let expected: Vec<Node> = vec![Section("section", vec![Unescaped("child_tag", "".to_string())], false, "{{&section.child_tag}}".to_string(), "".to_string())];
         assert_eq!(nodes, expected);
     }
 
@@ -309,9 +299,24 @@ mod parser_tests {
         let expected: Vec<Node> = vec![
             Section("section", vec![
                 Section("child", vec![
-                    Value("tag", "{{tag}}".to_string())]
-                    ,false, "{{#child}}".to_string(), "{{/child}}".to_string())]
-            , false, "{{#section}}".to_string(), "{{/section}}".to_string())];
+                    Value("tag", "".to_string())]
+                    ,false, "".to_string(), "".to_string())]
+            , false, "{{section.child.tag}}".to_string(), "".to_string())];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn parse_four_level_dot_notation() {
+        let tokens: Vec<Token> = vec![Variable("a.b.c.d", "{{ a.b.c.d }}")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![
+            Section("a", vec![
+                Section("b", vec![
+                    Section("c", vec![
+                        Value("d", "".to_string())]
+                        , false, "".to_string(), "".to_string())]
+                    , false, "".to_string(), "".to_string())]
+            , false, "{{a.b.c.d}}".to_string(), "".to_string())];
         assert_eq!(nodes, expected);
     }
 
@@ -322,9 +327,9 @@ mod parser_tests {
         let expected: Vec<Node> = vec![
             Section("section", vec![
                 Section("child", vec![
-                    Unescaped("tag", "{{{tag}}}".to_string())]
-                    ,false, "{{#child}}".to_string(), "{{/child}}".to_string())]
-            , false, "{{#section}}".to_string(), "{{/section}}".to_string())];
+                    Unescaped("tag", "".to_string())]
+                    ,false, "".to_string(), "".to_string())]
+            , false, "{{{section.child.tag}}}".to_string(), "".to_string())];
         assert_eq!(nodes, expected);
     }
 
@@ -335,9 +340,9 @@ mod parser_tests {
         let expected: Vec<Node> = vec![
             Section("section", vec![
                 Section("child", vec![
-                    Unescaped("tag", "{{&tag}}".to_string())]
-                    ,false, "{{#child}}".to_string(), "{{/child}}".to_string())]
-            , false, "{{#section}}".to_string(), "{{/section}}".to_string())];
+                    Unescaped("tag", "".to_string())]
+                    ,false, "".to_string(), "".to_string())]
+            , false, "{{&section.child.tag}}".to_string(), "".to_string())];
         assert_eq!(nodes, expected);
     }
 
@@ -373,6 +378,32 @@ mod parser_tests {
         assert_eq!(nodes, expected);
     }
 
+    #[test]
+    fn parse_section_with_else() {
+        let tokens: Vec<Token> = vec![OTag("section", false, "{{# section }}"), Text("a"), Else("empty", "{{: empty }}"), Variable("name", "{{ name }}"), CTag("section", "{{/ section }}")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![
+            Section("section", vec![Static("a")], false, "{{# section }}".to_string(), "{{: empty }}".to_string()),
+            Section("section", vec![Value("name", "{{ name }}".to_string())], true, "{{: empty }}".to_string(), "{{/ section }}".to_string())];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn structural_eq_ignores_tag_whitespace() {
+        let spaced = vec![OTag("section", false, "{{# section }}"), Variable("name", "{{ name }}"), CTag("section", "{{/ section }}")];
+        let tight = vec![OTag("section", false, "{{#section}}"), Variable("name", "{{name}}"), CTag("section", "{{/section}}")];
+        let other = vec![OTag("section", true, "{{^section}}"), Variable("name", "{{name}}"), CTag("section", "{{/section}}")];
+
+        let spaced_nodes = parser::parse_nodes(&spaced);
+        let tight_nodes = parser::parse_nodes(&tight);
+        let other_nodes = parser::parse_nodes(&other);
+
+        assert!(spaced_nodes != tight_nodes);
+        assert!(spaced_nodes[0].structural_eq(&tight_nodes[0]));
+        assert!(!spaced_nodes[0].structural_eq(&other_nodes[0]));
+        assert!(!Static(" ").structural_eq(&Static("  ")));
+    }
+
     #[test]
     fn parse_unescaped() {
         let tokens: Vec<Token> = vec![Raw("unescaped", "{{& unescaped }}")];
@@ -383,24 +414,73 @@ mod parser_tests {
 
     #[test]
     fn parse_partial() {
-        let tokens: Vec<Token> = vec![Partial("new","{{> new }}")];
+        let tokens: Vec<Token> = vec![Partial("new", "{{> new }}", "")];
         let nodes = parser::parse_nodes(&tokens);
-        let expected: Vec<Node> = vec![Part("new", "{{> new }}")];
+        let expected: Vec<Node> = vec![Part("new", "{{> new }}", "")];
         assert_eq!(nodes, expected);
     }
 
+    #[test]
+    fn parse_partial_indentation() {
+        let tokens: Vec<Token> = vec![Text("a\n"), Partial("new", "{{> new }}", "    ")];
+        let nodes = parser::parse_nodes(&tokens);
+        let expected: Vec<Node> = vec![Static("a\n"), Part("new", "{{> new }}", "    ")];
+        assert_eq!(nodes, expected);
+    }
+
+    #[test]
+    fn check_sections() {
+        let balanced = vec![OTag("a", false, "{{#a}}"), OTag("b", true, "{{^b}}"), CTag("b", "{{/b}}"), CTag("a", "{{/a}}")];
+        assert_eq!(Ok(()), parser::check_sections(&balanced));
+
+        let unclosed = vec![OTag("a", false, "{{#a}}"), Text("x")];
+        assert_eq!(Err("unclosed section a".to_string()), parser::check_sections(&unclosed));
+
+        let crossed = vec![OTag("a", false, "{{#a}}"), OTag("b", false, "{{#b}}"), CTag("a", "{{/a}}"), CTag("b", "{{/b}}")];
+        assert_eq!(Err("section b closed by a".to_string()), parser::check_sections(&crossed));
+
+        let unopened = vec![CTag("a", "{{/a}}")];
+        assert_eq!(Err("section a closed without being opened".to_string()), parser::check_sections(&unopened));
+    }
+
+    #[test]
+    fn parse_checked_unclosed_section() {
+        let tokens = vec![Text("a"), OTag("items", false, "{{#items}}"), Variable("name", "{{name}}")];
+        assert_eq!(Err("unclosed section items".to_string()), parser::parse_checked(&tokens));
+    }
+
+    #[test]
+    fn parse_checked_close_not_matching_nearest_open() {
+        let tokens = vec![OTag("outer", false, "{{#outer}}"), OTag("inner", false, "{{#inner}}"),
+                          CTag("outer", "{{/outer}}"), CTag("inner", "{{/inner}}")];
+        assert_eq!(Err("section inner closed by outer".to_string()), parser::parse_checked(&tokens));
+
+        let balanced = vec![OTag("outer", false, "{{#outer}}"), Text("x"), CTag("outer", "{{/outer}}")];
+        assert_eq!(Ok(parser::parse_nodes(&balanced)), parser::parse_checked(&balanced));
+    }
+
+    #[test]
+    fn compile_matches_two_step_parse() {
+        let contents = "Hi {{name}}{{#items}}<{{&.}}>{{:none}}none{{/items}}{{> footer }}{{a.b}}";
+        let tokens = compiler::create_tokens(contents);
+
+        assert_eq!(parser::parse_nodes(&tokens), parser::compile(contents));
+        assert_eq!(Ok(parser::parse_nodes(&tokens)), parser::compile_checked(contents));
+        assert_eq!(Err("unclosed section items".to_string()), parser::compile_checked("{{#items}}"));
+    }
+
     #[test]
     fn parse_all() {
         let tokens: Vec<Token> = vec![
             Text("Static String "), Variable("token", "{{ token }}"), OTag("section", false, "{{# section }}"),
             Variable("child_tag", "{{ child_tag }}"), CTag("section", "{{/ section }}"),
-            Partial("new","{{> new }}"), Raw("unescaped", "{{& unescaped }}")
+            Partial("new", "{{> new }}", ""), Raw("unescaped", "{{& unescaped }}")
         ];
         let nodes = parser::parse_nodes(&tokens);
         let static_node = Static("Static String ");
         let value_node = Value("token", "{{ token }}".to_string());
         let section_node = Section("section", vec![Value("child_tag", "{{ child_tag }}".to_string())], false, "{{# section }}".to_string(), "{{/ section }}".to_string());
-        let file_node = Part("new", "{{> new }}");
+        let file_node = Part("new", "{{> new }}", "");
         let undescaped_node = Unescaped("unescaped", "{{& unescaped }}".to_string());
         let expected: Vec<Node> = vec![static_node, value_node, section_node, file_node, undescaped_node];
         assert_eq!(nodes, expected);
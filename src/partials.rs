@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::Path;
+use std::collections::HashMap;
+
+use compiler;
+use parser;
+use rustache::read_file;
+use template::TemplateError::InvalidTemplate;
+
+use RustacheResult;
+use RustacheError::{FileError, TemplateErrorType};
+
+/// `PartialCache` holds partial templates by name, so they can be checked
+/// once up front and rendered without touching the filesystem again.  The
+/// partials are kept as text and compiled again each time they render.
+#[derive(Debug)]
+pub struct PartialCache {
+    partials: HashMap<String, String>
+}
+
+impl PartialCache {
+    /// Create a new, empty `PartialCache`
+    pub fn new() -> PartialCache {
+        PartialCache {
+            partials: HashMap::new()
+        }
+    }
+
+    /// Read every `.mustache` file in a directory and check that its tags
+    /// line up, adding each one under its file name without the extension.
+    /// Stops at the first file that can't be read or checked, and the error
+    /// names that file.  Nothing is added unless every file is fine.  The
+    /// files are only checked here, not kept compiled.
+    ///
+    /// ```ignore
+    /// let mut cache = rustache::PartialCache::new();
+    /// cache.preload_dir("templates/partials").unwrap();
+    /// ```
+    pub fn preload_dir(&mut self, path: &str) -> RustacheResult<()> {
+        let entries = match fs::read_dir(path) {
+            Ok(entries) => entries,
+            Err(err) => return Err(FileError(format!("{}: \"{}\"", err, path)))
+        };
+
+        // directory order is arbitrary, sort so the first error is stable
+        let mut files = Vec::new();
+        for entry in entries {
+            match entry {
+                Ok(entry) => files.push(entry.path()),
+                Err(err) => return Err(FileError(format!("{}: \"{}\"", err, path)))
+            }
+        }
+        files.sort();
+
+        let mut loaded = HashMap::new();
+        for file in files.iter() {
+            match file.extension().and_then(|ext| ext.to_str()) {
+                Some("mustache") => {},
+                _ => continue
+            }
+            let name = match file.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue
+            };
+
+            let contents = match read_file(file) {
+                Ok(contents) => contents,
+                Err(err) => return Err(FileError(err))
+            };
+            try!(check_partial(file, &contents));
+            loaded.insert(name, contents);
+        }
+
+        self.partials.extend(loaded);
+        Ok(())
+    }
+
+    /// Get the contents of a partial by name
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.partials.get(name).map(|contents| &contents[..])
+    }
+
+    /// The partials by name, as `render_text_with_partials` takes them
+    pub fn partials(&self) -> &HashMap<String, String> {
+        &self.partials
+    }
+}
+
+// compiles a partial, failing with the file name if its tags don't line up
+fn check_partial(file: &Path, contents: &str) -> RustacheResult<()> {
+    let tokens = compiler::create_tokens(contents);
+    match parser::check_sections(&tokens) {
+        Ok(()) => Ok(()),
+        Err(msg) => Err(TemplateErrorType(InvalidTemplate(format!("{}: {}", file.display(), msg))))
+    }
+}
+
+#[cfg(test)]
+mod partials_tests {
+    use partials::PartialCache;
+
+    #[test]
+    fn test_preload_dir_reports_bad_file() {
+        let mut cache = PartialCache::new();
+        let rv = cache.preload_dir("test_data/preload");
+
+        let msg = format!("{:?}", rv.unwrap_err());
+        assert!(msg.contains("bad.mustache"));
+        assert!(msg.contains("unclosed section names"));
+        // all_right sorts before the bad file, but isn't kept either
+        assert_eq!(None, cache.get("all_right"));
+        assert!(cache.partials().is_empty());
+    }
+
+    #[test]
+    fn test_preload_dir() {
+        let mut cache = PartialCache::new();
+        cache.preload_dir("test_data/preload_ok").unwrap();
+
+        assert_eq!(Some("Hi {{#names}}{{.}} {{/names}}"), cache.get("greet"));
+        assert_eq!(None, cache.get("missing"));
+    }
+}
@@ -1,37 +1,65 @@
 extern crate memstream;
 
 use std::fs::File;
-use std::io::Read;
+use std::io;
+use std::io::{Read, Write};
 use std::path::Path;
+use std::collections::HashMap;
+use std::borrow::Cow;
+use std::mem;
+use std::cmp::min;
 use compiler;
+use compiler::TemplateNames;
+use compiler::Token::Text;
 use parser;
+use parser::Node::Section;
 use self::memstream::MemStream;
 use rustc_serialize::json::Json;
-use rustc_serialize::json::Json::{Boolean, Null, I64, U64, F64, String, Array, Object};
-use build::{HashBuilder, VecBuilder};
-use template::Template;
+use build::HashBuilder;
+#[cfg(any(feature = "serde", feature = "yaml"))]
+use build::integer_data;
+use template::{Template, FinalNewline};
+use template::TemplateError::{StreamWriteError, OutputLimitExceeded, InvalidTemplate};
 
 use RustacheResult;
-use RustacheError::{JsonError, FileError};
+use RustacheError;
+use Data;
+use OrderedMap;
+use Data::Hash;
+use RustacheError::{JsonError, FileError, TemplateErrorType};
 
-/// Defines a `renderable` trait, so that all of our data is renderable
+/// Defines a `renderable` trait, so that all of our data is renderable.
+/// Your own types can implement it too, usually by building a `HashBuilder`
+/// and rendering that, and then be passed to `render_text`, `render_file`
+/// or `render_to_string`.
 pub trait Render<R: Read> {
     /// `render` function on a `renderable` returns a `reader`
     fn render(&self, template: &str) -> RustacheResult<R>;
+
+    /// `render_in` renders a template read from the given file, so that
+    /// partials are found next to it and escaping follows its extension
+    fn render_in(&self, template: &str, _path: &Path) -> RustacheResult<R> {
+        self.render(template)
+    }
 }
 
 /// Implement the `renderable` trait on the HashBuilder type
 impl<'a> Render<MemStream> for HashBuilder<'a> {
     fn render(&self, template: &str) -> RustacheResult<MemStream> {
+        self.render_in(template, Path::new(""))
+    }
+
+    fn render_in(&self, template: &str, path: &Path) -> RustacheResult<MemStream> {
         // Create the stream we are going to write to.
         let mut stream = MemStream::new();
 
         // Create our nodes
-        let tokens = compiler::create_tokens(template);
-        let nodes = parser::parse_nodes(&tokens);
+        let nodes = try!(compile_template(template));
         
         // Write to our stream.
-        try!(Template::new().render_data(&mut stream, self, &nodes));
+        let mut tmpl = Template::new();
+        tmpl.set_template_file(path);
+        try!(tmpl.render_data(&mut stream, self, &nodes));
         
         // Return the stream as a Reader.
         Ok(stream)
@@ -39,15 +67,29 @@ impl<'a> Render<MemStream> for HashBuilder<'a> {
 }
 
 
-/// Implement the `renderable` trait on the JSON type
+/// Implement the `renderable` trait on the JSON type.  The JSON must be an
+/// object, and its values are converted as `Data::from` converts them.
+///
+/// A `null` is kept as `Null` rather than left out.  It renders as nothing,
+/// or as `null` with `HashBuilder::set_null_rendering`, and is falsy in a
+/// section.  Unlike a missing key it isn't an error with strict variables,
+/// and the lookup stops there instead of going on to enclosing sections.
 impl Render<MemStream> for Json {
     fn render(&self, template: &str) -> RustacheResult<MemStream> {
-       parse_json(self).render(template)
+       try!(json_to_builder(self)).render(template)
+    }
+
+    fn render_in(&self, template: &str, path: &Path) -> RustacheResult<MemStream> {
+       try!(json_to_builder(self)).render_in(template, path)
     }
 }
 
 impl Render<MemStream> for Path {
     fn render(&self, template: &str) -> RustacheResult<MemStream> {
+        self.render_in(template, Path::new(""))
+    }
+
+    fn render_in(&self, template: &str, path: &Path) -> RustacheResult<MemStream> {
 
         return match read_file(self) {
             Ok(text) => {
@@ -57,8 +99,8 @@ impl Render<MemStream> for Path {
                     Err(err) => return Err(JsonError(format!("Invalid JSON. {}", err)))
                 };
 
-                let hb = parse_json(&json);
-                hb.render(template)
+                let hb = try!(json_to_builder(&json));
+                hb.render_in(template, path)
             },
             Err(err) => {
                 Err(FileError(err))
@@ -69,18 +111,44 @@ impl Render<MemStream> for Path {
 
 impl Render<MemStream> for String {
     fn render(&self, template: &str) -> RustacheResult<MemStream> {
+        self.render_in(template, Path::new(""))
+    }
+
+    fn render_in(&self, template: &str, path: &Path) -> RustacheResult<MemStream> {
 
         let json = match Json::from_str(&self[..]) {
             Ok(json) => json,
             Err(err) => return Err(JsonError(format!("Invalid JSON. {}", err)))
         };
 
-        let hb = parse_json(&json);
-        hb.render(template)
+        let hb = try!(json_to_builder(&json));
+        hb.render_in(template, path)
     }
 }
 
-/// Render a template from the given template file
+/// Implement the `renderable` trait on a plain map of strings.  Each entry
+/// becomes a string value in the data, as `HashBuilder::insert_string`
+/// would insert it, so values are escaped as usual.
+impl Render<MemStream> for HashMap<::std::string::String, ::std::string::String> {
+    fn render(&self, template: &str) -> RustacheResult<MemStream> {
+        self.render_in(template, Path::new(""))
+    }
+
+    fn render_in(&self, template: &str, path: &Path) -> RustacheResult<MemStream> {
+        let mut hb = HashBuilder::new();
+        for (key, value) in self.iter() {
+            hb = hb.insert_string(&key[..], &value[..]);
+        }
+        hb.render_in(template, path)
+    }
+}
+
+/// Render a template from the given template file.  Partials are looked
+/// up next to the template file unless the data sets a partials path, and a
+/// partial name without an extension means a `.mustache` file.
+///
+/// Unless the data sets an escape mode, `.html` and `.htm` files are escaped
+/// as HTML, `.xml` files as XML, and `.txt` files aren't escaped at all.
 ///
 /// ```ignore
 /// rustache::render_file("path/to/template.html", &data);
@@ -88,11 +156,28 @@ impl Render<MemStream> for String {
 pub fn render_file<R: Read, Re: Render<R>>(path: &str, renderable: Re) -> RustacheResult<R> {
 
     return match read_file(&Path::new(path)) {
-        Ok(text) => renderable.render(&text[..]),
+        Ok(text) => renderable.render_in(&text[..], &Path::new(path)),
         Err(err) => Err(FileError(err))
     }
 }
 
+/// Render a template from the given template file with the given data,
+/// handing back the output as a `String`.  Partials and escaping work as in
+/// `render_file`, and a file that can't be read is a `FileError`.
+///
+/// ```ignore
+/// let output = rustache::render_file_to_string("path/to/template.html", &data);
+/// ```
+pub fn render_file_to_string(path: &str, data: &HashBuilder) -> RustacheResult<String> {
+    let text = match read_file(&Path::new(path)) {
+        Ok(text) => text,
+        Err(err) => return Err(FileError(err))
+    };
+    let stream = try!(data.render_in(&text[..], &Path::new(path)));
+
+    output_to_string(stream.unwrap())
+}
+
 /// Render the given template string
 ///
 /// ```ignore
@@ -102,105 +187,567 @@ pub fn render_text<R: Read, Re: Render<R>>(input: &str, renderable: Re) -> Rusta
     renderable.render(input)
 }
 
-// parses a Rust JSON hash and matches all possible types that may be passed in
-// returning a HashBuilder 
-fn parse_json(json: &Json) -> HashBuilder {
-    let mut data = HashBuilder::new();
-    for (k, v) in json.as_object().unwrap().iter() {
-        match v {
-            &I64(num) => {
-                data = data.insert_string(&k[..], num.to_string());
+/// Render the given template string, returning the output of each named
+/// top-level section alongside the full render under the `""` key.  A
+/// section's fragment includes its `{{:else}}` branch and any inverted
+/// section of the same name right after it.  Inverted sections on their
+/// own, dotted names and the `{{#set}}`, `{{#repeat}}` and `{{#each}}`
+/// built-ins get no fragment, and a section written twice keeps its first
+/// fragment.
+///
+/// ```ignore
+/// let fragments = rustache::render_fragments("{{#header}}..{{/header}}", &data);
+/// ```
+pub fn render_fragments(template: &str, data: &HashBuilder) -> RustacheResult<HashMap<String, String>> {
+    let nodes = try!(compile_template(template));
+    let mut fragments = HashMap::new();
+
+    // The full render is always available under the empty key
+    let full = try!(render_nodes_to_string(data, &nodes));
+    fragments.insert("".to_string(), full);
+
+    // Each top-level section is rendered on its own against the same data,
+    // together with its else branch and any {{^name}} right after it.  An
+    // else branch opens with the tag that closed the section before it.
+    let mut i = 0;
+    while i < nodes.len() {
+        let (name, inverted, mut close) = match nodes[i] {
+            Section(name, _, inverted, _, ref close) if fragment_section(name, close) => (name, inverted, close),
+            _ => {
+                i += 1;
+                continue;
             }
-            &U64(num) => {
-                data = data.insert_string(&k[..], num.to_string());
-            },
-            &F64(num) => {
-                data = data.insert_string(&k[..], num.to_string());
-            },
-            &Boolean(val) => {
-                data = data.insert_bool(&k[..], val);
-            },
-            &Array(ref list) => {
-                data = data.insert_vector(&k[..], |mut builder| {
-                    for item in list.iter() {
-                        builder = match *item {
-                            Object(_) => builder.push_hash(|_| {
-                                parse_json(item)
-                            }),
-                            Array(_) => builder.push_vector(|_| {
-                                parse_json_vector(item)
-                            }),
-                            String(_) => builder.push_string(item.as_string().unwrap()),
-                            Boolean(_) => builder.push_bool(item.as_boolean().unwrap()),
-                            _ => builder
-                        }
-                    }
-                    builder
-                });
-            },
-            &Object(_) => {
-                data = data.insert_hash(&k[..], |_| {
-                    parse_json(v)
-                });
-            },
-            &Null => {},
-            &String(ref text) => {
-                data = data.insert_string(&k[..], &text[..]);
-            },
+        };
+
+        let mut end = i + 1;
+        while end < nodes.len() {
+            match nodes[end] {
+                Section(sibling, _, sibling_inverted, ref open, ref sibling_close)
+                    if sibling == name && (sibling_inverted || open == close) => {
+                    close = sibling_close;
+                    end += 1;
+                },
+                _ => break
+            }
+        }
+
+        if !inverted && !fragments.contains_key(name) {
+            let fragment = try!(render_nodes_to_string(data, &nodes[i..end].to_vec()));
+            fragments.insert(name.to_string(), fragment);
+        }
+        i = end;
+    }
+
+    Ok(fragments)
+}
+
+// Whether a top-level section is one written in the template as
+// {{#name}}, rather than a dotted name, a built-in or a dev-only region
+fn fragment_section(name: &str, close: &str) -> bool {
+    !close.is_empty() && name != compiler::DEV_ONLY &&
+        compiler::set_target(name).is_none() && compiler::repeat_count(name).is_none() &&
+        compiler::each_target(name).is_none()
+}
+
+/// Render the given template string, borrowing the template itself when
+/// it contains no tags and so renders as-is.  When the data asks for HTML
+/// minifying or a final newline policy the output is always owned, since
+/// those change even a template without tags.
+///
+/// ```ignore
+/// let output = rustache::render_cow("Hello, world!", &data);
+/// ```
+pub fn render_cow<'t>(template: &'t str, data: &HashBuilder) -> RustacheResult<Cow<'t, str>> {
+    let tokens = compiler::create_tokens(template);
+
+    // A template made up only of text has nothing to substitute, but may
+    // still be changed on its way out
+    let tagless = tokens.iter().all(|token| match *token {
+        Text(_) => true,
+        _ => false
+    });
+    let post_processed = data.html_minify || data.final_newline != FinalNewline::Keep;
+    if tagless && !post_processed {
+        return Ok(Cow::Borrowed(template));
+    }
+
+    let nodes = match parser::parse_checked(&tokens) {
+        Ok(nodes) => nodes,
+        Err(msg) => return Err(TemplateErrorType(InvalidTemplate(msg)))
+    };
+    let output = try!(render_nodes_to_string(data, &nodes));
+    Ok(Cow::Owned(output))
+}
+
+/// Render the given template string with all of the data nested under a
+/// namespace key, so that it is reached with `{{ namespace.key }}`
+///
+/// ```ignore
+/// rustache::render_namespaced("{{ page.title }}", "page", data);
+/// ```
+pub fn render_namespaced(template: &str, namespace: &str, data: HashBuilder) -> RustacheResult<MemStream> {
+    // Keep the render settings, only the data moves under the namespace
+    let mut data = data;
+    let inner = mem::replace(&mut data.data, OrderedMap::new());
+    data.insert_data(namespace.to_string(), Hash(inner));
+
+    data.render(template)
+}
+
+/// Render the given template string as far as possible, collecting every
+/// error along the way instead of stopping at the first one.  Each failed
+/// tag renders as a placeholder describing its error.
+///
+/// ```ignore
+/// let (output, errors) = rustache::render_collect_errors("{{ name }}", &data);
+/// ```
+pub fn render_collect_errors(template: &str, data: &HashBuilder) -> (String, Vec<RustacheError>) {
+    let tokens = compiler::create_tokens(template);
+    let nodes = parser::parse_nodes(&tokens);
+    let mut stream = MemStream::new();
+    let mut errors = Template::new().render_data_collect_errors(&mut stream, data, &nodes);
+    // the sections that could be parsed still render, after the tag error
+    match parser::check_sections(&tokens) {
+        Ok(()) => {},
+        Err(msg) => errors.insert(0, TemplateErrorType(InvalidTemplate(msg)))
+    }
+
+    let output = match output_to_string(stream.unwrap()) {
+        Ok(text) => text,
+        Err(err) => {
+            errors.push(err);
+            String::new()
+        }
+    };
+
+    (output, errors)
+}
+
+/// Render the given template string, reading the output back as a
+/// `String`.  Works with any `Render` data, and output that isn't valid UTF-8
+/// is a `StreamWriteError`.
+///
+/// ```rust
+/// use rustache::HashBuilder;
+/// let data = HashBuilder::new().insert_string("name", "Anduin");
+/// assert_eq!("Hello, Anduin!".to_string(), rustache::render_to_string("Hello, {{ name }}!", data).unwrap());
+/// ```
+pub fn render_to_string<R: Read, Re: Render<R>>(template: &str, renderable: Re) -> RustacheResult<String> {
+    let mut reader = try!(renderable.render(template));
+    let mut output = Vec::new();
+    match reader.read_to_end(&mut output) {
+        Ok(_) => {},
+        Err(err) => return Err(TemplateErrorType(StreamWriteError(format!("{}", err))))
+    }
+
+    output_to_string(output)
+}
+
+/// Render the given template string with the given data, handing back the
+/// output as a `String`.
+///
+/// ```ignore
+/// let data = HashBuilder::new().insert_string("name", "Anduin");
+/// rustache::render_string_from_hash("Hello, {{ name }}!", &data);
+/// ```
+pub fn render_string_from_hash(template: &str, data: &HashBuilder) -> RustacheResult<String> {
+    let nodes = try!(compile_template(template));
+    render_nodes_to_string(data, &nodes)
+}
+
+/// Render the given template string with partials looked up by name in
+/// the given map instead of on the filesystem.  Partials may include other
+/// registered partials.
+///
+/// ```ignore
+/// let mut partials = HashMap::new();
+/// partials.insert("user".to_string(), "<b>{{ name }}</b>".to_string());
+/// rustache::render_text_with_partials("Hello, {{> user }}!", &data, &partials);
+/// ```
+pub fn render_text_with_partials(template: &str, data: &HashBuilder, partials: &HashMap<String, String>) -> RustacheResult<String> {
+    let nodes = try!(compile_template(template));
+    let mut stream = MemStream::new();
+    let mut tmpl = Template::new();
+    tmpl.set_partials(partials);
+    try!(tmpl.render_data(&mut stream, data, &nodes));
+
+    output_to_string(stream.unwrap())
+}
+
+/// Render the given template string, handing back the output along with
+/// the data.  Each `{{#set name}}...{{/set}}` section writes nothing, its
+/// rendered body is inserted into the returned data as `name` instead, so
+/// it can be used by a later render.
+///
+/// ```ignore
+/// let (output, context) = rustache::render_with_context("{{#set title}}Hi {{ name }}{{/set}}", data).unwrap();
+/// ```
+pub fn render_with_context<'a>(template: &str, data: HashBuilder<'a>) -> RustacheResult<(String, HashBuilder<'a>)> {
+    let nodes = try!(compile_template(template));
+    let mut stream = MemStream::new();
+
+    let assigned = {
+        let mut tmpl = Template::new();
+        try!(tmpl.render_data(&mut stream, &data, &nodes));
+        tmpl.take_assigned()
+    };
+
+    let output = try!(output_to_string(stream.unwrap()));
+
+    let mut data = data;
+    for (key, value) in assigned.into_iter() {
+        data = data.insert_string(key, value);
+    }
+
+    Ok((output, data))
+}
+
+/// List the value, section and partial names the given template refers
+/// to, without rendering it.  A template with unclosed or mismatched
+/// sections is an `InvalidTemplate` error.
+///
+/// ```rust
+/// let names = rustache::template_names("{{#users}}{{ name }}{{/users}}{{> footer }}").unwrap();
+/// assert_eq!(vec!["name"], names.values);
+/// assert_eq!(vec!["users"], names.sections);
+/// assert_eq!(vec!["footer"], names.partials);
+/// ```
+pub fn template_names(template: &str) -> RustacheResult<TemplateNames> {
+    let tokens = compiler::create_tokens(template);
+    match parser::check_sections(&tokens) {
+        Ok(()) => Ok(compiler::template_names(&tokens)),
+        Err(msg) => Err(TemplateErrorType(InvalidTemplate(msg)))
+    }
+}
+
+/// `CompiledTemplate` holds a template that has already been compiled, so
+/// it can be rendered any number of times without being parsed again
+#[derive(Debug)]
+pub struct CompiledTemplate<'t> {
+    nodes: Vec<parser::Node<'t>>
+}
+
+impl<'t> CompiledTemplate<'t> {
+    /// Compile the given template string, failing if a section is left
+    /// unclosed or closed by the wrong tag
+    ///
+    /// ```rust
+    /// use rustache::{CompiledTemplate, HashBuilder};
+    /// let template = CompiledTemplate::new("Hello, {{ name }}!").unwrap();
+    /// let data = HashBuilder::new().insert_string("name", "Anduin");
+    /// assert_eq!("Hello, Anduin!".to_string(), template.render(&data).unwrap());
+    /// ```
+    pub fn new(template: &'t str) -> RustacheResult<CompiledTemplate<'t>> {
+        Ok(CompiledTemplate {
+            nodes: try!(compile_template(template))
+        })
+    }
+
+    /// Render the compiled template with the given data, handing back the
+    /// output as a `String`
+    pub fn render(&self, data: &HashBuilder) -> RustacheResult<String> {
+        render_nodes_to_string(data, &self.nodes)
+    }
+
+    /// Render the compiled template with the given data into any writer
+    ///
+    /// ```ignore
+    /// let mut out = Vec::new();
+    /// template.render_to(&mut out, &data).unwrap();
+    /// ```
+    pub fn render_to<W: Write>(&self, writer: &mut W, data: &HashBuilder) -> RustacheResult<()> {
+        Template::new().render_data(writer, data, &self.nodes)
+    }
+
+    /// Return whether two compiled templates have the same structure, the
+    /// same text and tags in the same places, ignoring whitespace and
+    /// delimiters inside the tags
+    ///
+    /// ```rust
+    /// use rustache::CompiledTemplate;
+    /// let spaced = CompiledTemplate::new("{{# user }}{{ name }}{{/ user }}").unwrap();
+    /// let tight = CompiledTemplate::new("{{=<% %>=}}<%#user%><%name%><%/user%>").unwrap();
+    /// assert!(spaced.structural_eq(&tight));
+    /// ```
+    pub fn structural_eq(&self, other: &CompiledTemplate) -> bool {
+        self.nodes.len() == other.nodes.len() &&
+            self.nodes.iter().zip(other.nodes.iter()).all(|(a, b)| a.structural_eq(b))
+    }
+}
+
+/// Render the given template string into the given buffer, handing back
+/// the number of bytes written.  Output that doesn't fit in the buffer is an
+/// `OutputLimitExceeded` error giving the number of bytes written, with the
+/// buffer filled with as much of the output as fits.
+///
+/// ```ignore
+/// let mut buf = [0u8; 64];
+/// let len = rustache::render_to_buf("Hello, {{ name }}!", &data, &mut buf).unwrap();
+/// ```
+pub fn render_to_buf(template: &str, data: &HashBuilder, buf: &mut [u8]) -> RustacheResult<usize> {
+    let nodes = try!(compile_template(template));
+    let limit = buf.len();
+    let mut writer = SliceWriter { buf: buf, written: 0, overflowed: false };
+
+    let rv = Template::new().render_data(&mut writer, data, &nodes);
+    if writer.overflowed {
+        let msg = format!("more than {} bytes, {} written", limit, writer.written);
+        return Err(TemplateErrorType(OutputLimitExceeded(msg)));
+    }
+    try!(rv);
+
+    Ok(writer.written)
+}
+
+// compiles a template into nodes, failing if its section tags don't line up
+fn compile_template(template: &str) -> RustacheResult<Vec<parser::Node>> {
+    match parser::compile_checked(template) {
+        Ok(nodes) => Ok(nodes),
+        Err(msg) => Err(TemplateErrorType(InvalidTemplate(msg)))
+    }
+}
+
+// writes into a fixed slice, taking as much of each write as fits and
+// failing once the slice is full
+struct SliceWriter<'b> {
+    buf: &'b mut [u8],
+    written: usize,
+    overflowed: bool
+}
+
+impl<'b> Write for SliceWriter<'b> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let room = self.buf.len() - self.written;
+        if room == 0 && !data.is_empty() {
+            self.overflowed = true;
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "buffer full"));
+        }
+        let len = min(room, data.len());
+        self.buf[self.written .. self.written + len].copy_from_slice(&data[..len]);
+        self.written += len;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Render the given template string against a table of rows.  Each row
+/// becomes a hash mapping the headers to its values, and the rows are
+/// reached with a `{{#rows}}` section.
+///
+/// ```ignore
+/// let rows = vec![vec!["Ada".to_string(), "Lovelace".to_string()]];
+/// rustache::render_rows("{{#rows}}{{first}} {{last}}{{/rows}}", &["first", "last"], &rows);
+/// ```
+pub fn render_rows(template: &str, headers: &[&str], rows: &[Vec<String>]) -> RustacheResult<String> {
+    let data = HashBuilder::new().insert_vector("rows", |mut builder| {
+        for row in rows.iter() {
+            builder = builder.push_hash(|mut hash| {
+                // a short row just leaves the remaining headers out
+                for (header, value) in headers.iter().zip(row.iter()) {
+                    hash = hash.insert_string(*header, &value[..]);
+                }
+                hash
+            });
         }
+        builder
+    });
+
+    let nodes = try!(compile_template(template));
+    render_nodes_to_string(&data, &nodes)
+}
+
+/// Render the given template string against a `serde_json::Value`.  Objects
+/// become hashes, arrays become vectors, and numbers become integers or
+/// floats.  The value must be an object.  Requires the `serde` feature.
+///
+/// ```ignore
+/// let value = json!({ "name": "Anduin" });
+/// rustache::render_serde("Hello, {{ name }}!", &value);
+/// ```
+#[cfg(feature = "serde")]
+pub fn render_serde(template: &str, value: &::serde_json::Value) -> RustacheResult<String> {
+    let mut data = HashBuilder::new();
+    match *value {
+        ::serde_json::Value::Object(ref object) => {
+            for (key, item) in object.iter() {
+                data.insert_data(key.clone(), serde_to_data(item));
+            }
+        },
+        _ => return Err(JsonError("Invalid JSON. Expected an object".to_string()))
     }
 
-    data
+    let nodes = try!(compile_template(template));
+    render_nodes_to_string(&data, &nodes)
 }
 
-// parses a Rust JSON vector and matches all possible types that may be passed in
-// returning a VecBuider
-fn parse_json_vector(json: &Json) -> VecBuilder {
-    let mut data = VecBuilder::new();
-    for v in json.as_array().unwrap().iter() {
-        match v {
-            &I64(num) => {
-                data = data.push_string(num.to_string());
+// converts a serde value into the matching Data type, keeping numbers
+// that fit in an i32 as integers
+#[cfg(feature = "serde")]
+fn serde_to_data<'a>(value: &::serde_json::Value) -> Data<'a> {
+    match *value {
+        ::serde_json::Value::Null => Data::Null,
+        ::serde_json::Value::Bool(val) => Data::Bool(val),
+        ::serde_json::Value::Number(ref num) => {
+            match num.as_i64() {
+                Some(n) => integer_data(n),
+                None => Data::Float(num.as_f64().unwrap_or(0.0))
             }
-            &U64(num) => {
-                data = data.push_string(num.to_string());
-            },
-            &F64(num) => {
-                data = data.push_string(num.to_string());
-            },
-            &Boolean(val) => {
-                data = data.push_bool(val);
-            },
-            &Array(ref list) => {
-                data = data.push_vector(|mut builder| {
-                    for item in list.iter() {
-                        builder = match *item {
-                            Object(_) => builder.push_hash(|_| {
-                                parse_json(item)
-                            }),
-                            Array(_) => builder.push_vector(|_| {
-                                parse_json_vector(item)
-                            }),
-                            String(_) => builder.push_string(item.as_string().unwrap()),
-                            Boolean(_) => builder.push_bool(item.as_boolean().unwrap()),
-                            _ => builder
-                        }
-                    }
-                    builder
-                });
-            },
-            &Object(_) => {
-                data = data.push_hash(|_| {
-                    parse_json(v)
-                });
-            },
-            &Null => {},
-            &String(ref text) => {
-                data = data.push_string(&text[..]);
-            },
+        },
+        ::serde_json::Value::String(ref text) => Data::Strng(text.clone()),
+        ::serde_json::Value::Array(ref list) => Data::Vector(list.iter().map(serde_to_data).collect()),
+        ::serde_json::Value::Object(ref object) => {
+            let mut hash = OrderedMap::new();
+            for (key, item) in object.iter() {
+                hash.insert(key.clone(), serde_to_data(item));
+            }
+            Hash(hash)
         }
     }
-    data
+}
+
+/// Render a template file that may begin with YAML front matter between
+/// `---` lines.  The front matter is left out of the output, and its keys
+/// are added to the data, replacing any already there.  Otherwise this works
+/// like `render_file`.  Requires the `yaml` feature.
+///
+/// ```ignore
+/// // page.html holds "---\ntitle: Heroes\n---\n<h1>{{ title }}</h1>"
+/// rustache::render_file_with_front_matter("page.html", data);
+/// ```
+#[cfg(feature = "yaml")]
+pub fn render_file_with_front_matter<'a>(path: &str, data: HashBuilder<'a>) -> RustacheResult<MemStream> {
+    let text = match read_file(&Path::new(path)) {
+        Ok(text) => text,
+        Err(err) => return Err(FileError(err))
+    };
+    let (yaml, body) = match split_front_matter(&text) {
+        Some(parts) => parts,
+        None => return data.render_in(&text[..], &Path::new(path))
+    };
+
+    let invalid = |msg: String| TemplateErrorType(InvalidTemplate(format!("{}: front matter: {}", path, msg)));
+    let docs = match ::yaml_rust::YamlLoader::load_from_str(yaml) {
+        Ok(docs) => docs,
+        Err(err) => return Err(invalid(format!("{}", err)))
+    };
+
+    let mut data = data;
+    match docs.into_iter().next() {
+        Some(::yaml_rust::Yaml::Hash(hash)) => {
+            for (key, value) in hash.into_iter() {
+                match yaml_key(key) {
+                    Some(key) => data.insert_data(key, yaml_to_data(value)),
+                    None => { }
+                }
+            }
+        },
+        // front matter with nothing in it
+        None => { },
+        Some(_) => return Err(invalid("expected key: value pairs".to_string()))
+    }
+
+    data.render_in(body, &Path::new(path))
+}
+
+// splits text starting with a --- line into the front matter up to the
+// next --- line and the rest of the text
+#[cfg(feature = "yaml")]
+fn split_front_matter(text: &str) -> Option<(&str, &str)> {
+    let rest = if text.starts_with("---\n") {
+        &text[4..]
+    } else if text.starts_with("---\r\n") {
+        &text[5..]
+    } else {
+        return None;
+    };
+
+    let mut start = 0;
+    for line in rest.split('\n') {
+        let end = start + line.len();
+        if line == "---" || line == "---\r" {
+            let body = if end < rest.len() { end + 1 } else { end };
+            return Some((&rest[..start], &rest[body..]));
+        }
+        start = end + 1;
+    }
+
+    None
+}
+
+// front matter keys are usually strings, but numbers and bools work too
+#[cfg(feature = "yaml")]
+fn yaml_key(key: ::yaml_rust::Yaml) -> Option<String> {
+    match key {
+        ::yaml_rust::Yaml::String(key) | ::yaml_rust::Yaml::Real(key) => Some(key),
+        ::yaml_rust::Yaml::Integer(key) => Some(key.to_string()),
+        ::yaml_rust::Yaml::Boolean(key) => Some(key.to_string()),
+        _ => None
+    }
+}
+
+// converts a YAML value into the matching Data type, keeping numbers
+// that fit in an i32 as integers
+#[cfg(feature = "yaml")]
+fn yaml_to_data<'a>(value: ::yaml_rust::Yaml) -> Data<'a> {
+    match value {
+        ::yaml_rust::Yaml::Integer(n) => integer_data(n),
+        ::yaml_rust::Yaml::Real(text) => {
+            match text.parse() {
+                Ok(num) => Data::Float(num),
+                Err(_) => Data::Strng(text)
+            }
+        },
+        ::yaml_rust::Yaml::String(text) => Data::Strng(text),
+        ::yaml_rust::Yaml::Boolean(val) => Data::Bool(val),
+        ::yaml_rust::Yaml::Array(list) => Data::Vector(list.into_iter().map(yaml_to_data).collect()),
+        ::yaml_rust::Yaml::Hash(hash) => {
+            let mut data = OrderedMap::new();
+            for (key, item) in hash.into_iter() {
+                match yaml_key(key) {
+                    Some(key) => { data.insert(key, yaml_to_data(item)); },
+                    None => { }
+                }
+            }
+            Hash(data)
+        },
+        _ => Data::Null
+    }
+}
+
+// renders a list of nodes into an in-memory stream and hands back the text
+fn render_nodes_to_string(data: &HashBuilder, nodes: &Vec<parser::Node>) -> RustacheResult<String> {
+    let mut stream = MemStream::new();
+    try!(Template::new().render_data(&mut stream, data, nodes));
+
+    output_to_string(stream.unwrap())
+}
+
+// turns rendered output into a string.  Templates and data are always
+// valid UTF-8, so a failure here means the output was mangled on the way.
+fn output_to_string(output: Vec<u8>) -> RustacheResult<String> {
+    match String::from_utf8(output) {
+        Ok(text) => Ok(text),
+        Err(err) => Err(TemplateErrorType(StreamWriteError(format!("render output is not valid UTF-8: {}", err))))
+    }
+}
+
+// builds the data for a JSON object, each value converted as Data::from
+// converts it
+fn json_to_builder<'a>(json: &Json) -> RustacheResult<HashBuilder<'a>> {
+    let mut data = HashBuilder::new();
+    match *json {
+        Json::Object(ref object) => {
+            for (key, value) in object.iter() {
+                data.insert_data(key.clone(), Data::from(value.clone()));
+            }
+        },
+        _ => return Err(JsonError("Invalid JSON. Expected an object".to_string()))
+    }
+
+    Ok(data)
 }
 
 // Hide from documentation
@@ -217,9 +764,521 @@ pub fn read_file(path: &Path) -> Result<String, String> {
     // Read the file contents into a heap allocated string
     let mut text = String::new();
     match file.read_to_string(&mut text) {
-        Err(why) => return { rv = Err(format!("{}", why)); return rv; },
+        Err(why) => { rv = Err(format!("{}: \"{}\"", why, display)); return rv; },
         Ok(_) => { rv = Ok(text); },
     };
 
     rv
 }
+
+#[cfg(test)]
+mod rustache_tests {
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+    use std::io::Cursor;
+
+    use rustache;
+    use build::HashBuilder;
+    use template::{EscapeMode, FinalNewline};
+    use RustacheResult;
+    use RustacheError::{FileError, TemplateErrorType};
+    use template::TemplateError::StreamWriteError;
+
+    #[test]
+    fn test_render_fragments() {
+        let data = HashBuilder::new()
+            .insert_hash("header", |h| { h.insert_string("title", "Home") })
+            .insert_hash("footer", |h| { h.insert_string("year", "2015") });
+
+        let fragments = rustache::render_fragments("{{#header}}<h1>{{title}}</h1>{{/header}}|{{#footer}}(c) {{year}}{{/footer}}", &data).unwrap();
+
+        assert_eq!(3, fragments.len());
+        assert_eq!("<h1>Home</h1>|(c) 2015".to_string(), fragments[""]);
+        assert_eq!("<h1>Home</h1>".to_string(), fragments["header"]);
+        assert_eq!("(c) 2015".to_string(), fragments["footer"]);
+    }
+
+    #[test]
+    fn test_render_fragments_else_and_inverted() {
+        let data = HashBuilder::new()
+            .insert_bool("x", true)
+            .insert_hash("a", |h| { h.insert_string("b", "B") });
+
+        let fragments = rustache::render_fragments("{{#x}}a{{:empty}}b{{/x}}|{{#y}}c{{/y}}{{^y}}d{{/y}}|{{a.b}}", &data).unwrap();
+
+        assert_eq!(3, fragments.len());
+        assert_eq!("a|d|B".to_string(), fragments[""]);
+        assert_eq!("a".to_string(), fragments["x"]);
+        assert_eq!("d".to_string(), fragments["y"]);
+        assert!(!fragments.contains_key("a"));
+    }
+
+    #[test]
+    fn test_render_fragments_skips_builtins() {
+        let data = HashBuilder::new().insert_string("name", "Bob");
+
+        let fragments = rustache::render_fragments("{{#set title}}{{name}}{{/set}}{{#repeat 2}}{{name}}{{/repeat}}\
+                                                    {{^z}}none{{:else}}some{{/z}}", &data).unwrap();
+
+        assert_eq!(1, fragments.len());
+        assert_eq!("BobBobnone".to_string(), fragments[""]);
+    }
+
+    #[test]
+    fn test_render_text_inserted_string() {
+        let data = HashBuilder::new().insert_string("name", "Jaina & Anduin");
+
+        let rv = rustache::render_text("Hello, {{ name }}!", data);
+
+        assert_eq!("Hello, Jaina &amp; Anduin!".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_text_string_map() {
+        let mut map = HashMap::new();
+        map.insert("name".to_string(), "Jaina & Anduin".to_string());
+        map.insert("city".to_string(), "Stormwind".to_string());
+
+        let rv = rustache::render_text("{{ name }} of {{ city }}{{ missing }}, {{& name }}", map);
+
+        assert_eq!("Jaina &amp; Anduin of Stormwind, Jaina & Anduin".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_text_strips_comments() {
+        let data = HashBuilder::new().insert_string("name", "Ada");
+
+        let rv = rustache::render_text("Hi{{!note}} {{name}}.", data);
+        assert_eq!("Hi Ada.".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+
+        let data = HashBuilder::new().insert_string("name", "Ada");
+        let rv = rustache::render_text("<p>{{! a comment with several words }}{{name}} wrote {{!this too}}code</p>", data);
+        assert_eq!("<p>Ada wrote code</p>".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_namespaced() {
+        let data = HashBuilder::new().insert_string("title", "Home")
+                                     .insert_hash("author", |h| { h.insert_string("name", "Ada") });
+
+        let rv = rustache::render_namespaced("{{ page.title }} by {{ page.author.name }}{{ title }}", "page", data);
+
+        assert_eq!("Home by Ada".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_collect_errors() {
+        let data = HashBuilder::new().insert_string("name", "Ada")
+                                     .set_strict_variables(true)
+                                     .set_strict_sections(true);
+
+        let (output, errors) = rustache::render_collect_errors("{{name}} {{missing}} {{#gone}}x{{/gone}}!", &data);
+
+        assert_eq!("Ada [UndefinedVariable(missing)] [UndefinedSection(gone)]!".to_string(), output);
+        assert_eq!(vec!["UndefinedVariable(missing)".to_string(), "UndefinedSection(gone)".to_string()],
+                   errors.iter().map(|err| format!("{:?}", err)).collect::<Vec<String>>());
+
+        // the placeholder is escaped like any other value
+        let data = HashBuilder::new().insert_vector("tags", |v| { v.push_string("<b>") });
+        let (output, errors) = rustache::render_collect_errors("{{tags}}", &data);
+        assert_eq!("[UnexpectedDataType(expecting text for Value(&quot;tags&quot;, &quot;{{tags}}&quot;), found vector data [Strng(&quot;&lt;b&gt;&quot;)])]".to_string(), output);
+        assert_eq!(1, errors.len());
+    }
+
+    #[test]
+    fn test_render_file_partial_next_to_template() {
+        let data = HashBuilder::new().insert_string("name", "Ada")
+                                     .insert_string("title", "Analyst");
+
+        let rv = rustache::render_file("test_data/profile.mustache", data);
+
+        assert_eq!("<h1>Ada</h1><p>Analyst</p>".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_text_with_partials() {
+        let data = HashBuilder::new().insert_string("name", "Ada")
+                                     .insert_vector("langs", |v| { v.push_string("Rust").push_string("C") });
+        let mut partials = HashMap::new();
+        partials.insert("user".to_string(), "<b>{{name}}</b> knows {{> langs }}".to_string());
+        partials.insert("langs".to_string(), "{{#langs}}[{{.}}]{{/langs}}".to_string());
+
+        let rv = rustache::render_text_with_partials("Hello, {{> user }}{{> missing }}!", &data, &partials);
+
+        assert_eq!("Hello, <b>Ada</b> knows [Rust][C]!".to_string(), rv.unwrap());
+    }
+
+    #[test]
+    fn test_partial_args_keep_lambdas() {
+        let mut f = |text: String| { format!("<b>{}</b>", text) };
+        let data = HashBuilder::new().insert_string("user", "Ada").insert_lambda("bold", &mut f);
+        let mut partials = HashMap::new();
+        partials.insert("card".to_string(), "{{#bold}}{{name}}{{/bold}}".to_string());
+
+        let rv = rustache::render_text_with_partials("{{> card name=user }}", &data, &partials);
+        assert_eq!("<b>Ada</b>".to_string(), rv.unwrap());
+
+        let rv = rustache::render_text_with_partials("{{=<% %>=}}<%> card name=user%>", &data, &partials);
+        assert_eq!("<b>Ada</b>".to_string(), rv.unwrap());
+    }
+
+    #[test]
+    fn test_partial_cycle() {
+        let data = HashBuilder::new().insert_vector("items", |v| {
+            v.push_hash(|h| { h.insert_string("name", "leaf").insert_vector("items", |v| { v }) })
+        });
+        let mut partials = HashMap::new();
+        partials.insert("page".to_string(), "<{{> a }}>".to_string());
+        partials.insert("a".to_string(), "a{{> b }}".to_string());
+        partials.insert("b".to_string(), "b{{> a }}".to_string());
+        // the same partial again for nested data isn't a cycle
+        partials.insert("tree".to_string(), "{{name}}({{#items}}{{> tree }}{{/items}})".to_string());
+
+        let rv = rustache::render_text_with_partials("{{> page }}", &data, &partials);
+        assert_eq!("Err(PartialCycle(partial cycle: a -> b -> a))".to_string(), format!("{:?}", rv));
+
+        let rv = rustache::render_text_with_partials("{{> tree }}", &data, &partials);
+        assert_eq!("(leaf())".to_string(), rv.unwrap());
+
+        // loops give every item a fresh scope, so these only stop at the depth limit
+        let data = HashBuilder::new().insert_vector("list", |v| { v.push_string("x") });
+        partials.insert("a".to_string(), "{{#list}}{{> a }}{{/list}}".to_string());
+        let rv = rustache::render_text_with_partials("{{> a }}", &data, &partials);
+        assert_eq!("Err(PartialCycle(partial cycle: a nested more than 50 deep))".to_string(), format!("{:?}", rv));
+
+        partials.insert("a".to_string(), "{{#repeat 1}}{{> a }}{{/repeat}}".to_string());
+        let rv = rustache::render_text_with_partials("{{> a }}", &data, &partials);
+        assert_eq!("Err(PartialCycle(partial cycle: a nested more than 50 deep))".to_string(), format!("{:?}", rv));
+    }
+
+    #[test]
+    fn test_unclosed_section() {
+        let data = HashBuilder::new().insert_string("name", "Ada");
+
+        let rv = rustache::render_string_from_hash("Hi {{#user}}{{name}}", &data);
+        assert_eq!("Err(InvalidTemplate(unclosed section user))".to_string(), format!("{:?}", rv));
+
+        let rv = rustache::render_string_from_hash("{{#user}}{{#admin}}{{name}}{{/user}}{{/admin}}", &data);
+        assert_eq!("Err(InvalidTemplate(section admin closed by user))".to_string(), format!("{:?}", rv));
+
+        let mut partials = HashMap::new();
+        partials.insert("user".to_string(), "{{#user}}{{name}}".to_string());
+        let rv = rustache::render_text_with_partials("<{{> user }}>", &data, &partials);
+        assert_eq!("Err(InvalidTemplate({{> user }}: unclosed section user))".to_string(), format!("{:?}", rv));
+    }
+
+    #[test]
+    fn test_mismatched_close_tag() {
+        let data = HashBuilder::new().insert_bool("a", true);
+
+        let rv = rustache::render_string_from_hash("{{#a}}yes{{/b}}", &data);
+        assert_eq!("Err(InvalidTemplate(section a closed by b))".to_string(), format!("{:?}", rv));
+
+        let rv = rustache::render_string_from_hash("{{#a}}yes{{/a}}{{/a}}", &data);
+        assert_eq!("Err(InvalidTemplate(section a closed without being opened))".to_string(), format!("{:?}", rv));
+
+        let (output, errors) = rustache::render_collect_errors("{{#a}}yes{{/a}} no{{/b}}", &data);
+        assert_eq!("yes no".to_string(), output);
+        assert_eq!("[InvalidTemplate(section b closed without being opened)]".to_string(), format!("{:?}", errors));
+    }
+
+    #[test]
+    fn test_crlf_standalone_tags() {
+        let data = HashBuilder::new().insert_vector("items", |v| {
+                                         v.push_hash(|h| { h.insert_string("name", "a") })
+                                          .push_hash(|h| { h.insert_string("name", "b") })
+                                     })
+                                     .insert_bool("none", false);
+        let mut partials = HashMap::new();
+        partials.insert("item".to_string(), "<li>{{name}}</li>\n\n".to_string());
+        partials.insert("item_crlf".to_string(), "<li>{{name}}</li>\r\n\r\n".to_string());
+
+        let lf = "<ul>\n  {{#items}}\n  {{> item }}\n  {{/items}}\n{{^none}}\nempty\n{{/none}}\n</ul>\n";
+        let crlf = "<ul>\r\n  {{#items}}\r\n  {{> item_crlf }}\r\n  {{/items}}\r\n{{^none}}\r\nempty\r\n{{/none}}\r\n</ul>\r\n";
+
+        let lf_output = rustache::render_text_with_partials(lf, &data, &partials).unwrap();
+        let crlf_output = rustache::render_text_with_partials(crlf, &data, &partials).unwrap();
+
+        assert_eq!("<ul>\n  <li>a</li>\n\n  <li>b</li>\n\nempty\n</ul>\n".to_string(), lf_output);
+        assert_eq!(lf_output.replace("\n", "\r\n"), crlf_output);
+    }
+
+    #[test]
+    fn test_template_names() {
+        let names = rustache::template_names("<h1>{{ title }}</h1>\n{{#users}}\n  {{#profile}}{{ name }}, {{ title }}{{/profile}}\n  {{> user_card }}\n{{/users}}\n{{^users}}{{ empty.message }}{{/users}}").unwrap();
+
+        assert_eq!(vec!["title", "name", "empty.message"], names.values);
+        assert_eq!(vec!["users", "profile"], names.sections);
+        assert_eq!(vec!["user_card"], names.partials);
+
+        let rv = rustache::template_names("{{#users}}{{name}}");
+        assert_eq!("Err(InvalidTemplate(unclosed section users))".to_string(), format!("{:?}", rv));
+    }
+
+    #[test]
+    fn test_render_with_context() {
+        let data = HashBuilder::new().insert_string("name", "Ada")
+                                     .insert_hash("user", |h| { h.insert_string("role", "admin") });
+
+        let (output, context) = rustache::render_with_context("{{#set title}}{{name}} the {{#user}}{{role}}{{/user}}{{/set}}<h1>{{name}}</h1>", data).unwrap();
+
+        assert_eq!("<h1>Ada</h1>".to_string(), output);
+        assert_eq!(context.keys(), vec!["name", "user", "title"]);
+        assert_eq!("Ada the admin".to_string(), rustache::render_string_from_hash("{{title}}", &context).unwrap());
+
+        let (output, context) = rustache::render_with_context("{{=<% %>=}}<%#set title%><%name%><%/set%>", context).unwrap();
+        assert_eq!("".to_string(), output);
+        assert_eq!("Ada".to_string(), rustache::render_string_from_hash("{{title}}", &context).unwrap());
+    }
+
+    #[test]
+    fn test_render_file_escape_by_extension() {
+        let render = |path: &str| {
+            let data = HashBuilder::new().insert_string("value", "a < b & 'c'");
+            String::from_utf8(rustache::render_file(path, data).unwrap().unwrap()).unwrap()
+        };
+
+        assert_eq!("<p>a &lt; b &amp; &#39;c&#39;</p>".to_string(), render("test_data/escape_by_extension.html"));
+        assert_eq!("<p>a < b & 'c'</p>".to_string(), render("test_data/escape_by_extension.txt"));
+        assert_eq!("<p>a &lt; b &amp; &apos;c&apos;</p>".to_string(), render("test_data/escape_by_extension.xml"));
+    }
+
+    #[test]
+    fn test_render_file_to_string() {
+        let data = HashBuilder::new().insert_string("value", "a < b");
+
+        assert_eq!("<p>a < b</p>".to_string(), rustache::render_string_from_hash("<p>{{& value}}</p>", &data).unwrap());
+        assert_eq!("<p>a &lt; b</p>".to_string(), rustache::render_file_to_string("test_data/escape_by_extension.html", &data).unwrap());
+
+        let rv = rustache::render_file_to_string("test_data/missing.html", &data);
+        match rv {
+            Err(FileError(msg)) => assert!(msg.contains("test_data/missing.html")),
+            _ => panic!("expected a FileError, got {:?}", rv)
+        }
+    }
+
+    #[test]
+    fn test_render_to_string() {
+        let data = HashBuilder::new().insert_string("name", "Jaina & «Anduin»");
+        assert_eq!("Hi, Jaina &amp; «Anduin»!".to_string(), rustache::render_to_string("Hi, {{ name }}!", data).unwrap());
+
+        let json = "{\"name\": \"Varian\"}".to_string();
+        assert_eq!("Hi, Varian!".to_string(), rustache::render_to_string("Hi, {{ name }}!", json).unwrap());
+
+        assert_eq!(Some("ok".to_string()), super::output_to_string(b"ok".to_vec()).ok());
+        let rv = super::output_to_string(vec![b'o', 0xff, b'k']);
+        match rv {
+            Err(TemplateErrorType(StreamWriteError(msg))) => assert!(msg.starts_with("render output is not valid UTF-8")),
+            _ => panic!("expected a StreamWriteError, got {:?}", rv)
+        }
+    }
+
+    // a user type rendered through its own Render impl, with a reader
+    // other than MemStream
+    struct Hero {
+        name: &'static str,
+        level: i32
+    }
+
+    impl rustache::Render<Cursor<Vec<u8>>> for Hero {
+        fn render(&self, template: &str) -> RustacheResult<Cursor<Vec<u8>>> {
+            let data = HashBuilder::new().insert_string("name", self.name)
+                                         .insert_int("level", self.level);
+            let output = try!(rustache::render_string_from_hash(template, &data));
+            Ok(Cursor::new(output.into_bytes()))
+        }
+    }
+
+    #[test]
+    fn test_render_to_string_custom_type() {
+        let hero = Hero { name: "Anduin", level: 60 };
+
+        let rv = rustache::render_to_string("{{name}} ({{level}})", hero);
+
+        assert_eq!("Anduin (60)".to_string(), rv.unwrap());
+    }
+
+    #[test]
+    fn test_render_file_error() {
+        let rv = rustache::render_file("test_data/missing.html", HashBuilder::new());
+        match rv {
+            Err(FileError(msg)) => {
+                assert!(msg.contains("test_data/missing.html"));
+                assert!(msg.contains("No such file"));
+            },
+            _ => panic!("expected a FileError")
+        }
+
+        // a directory opens fine but can't be read as a template
+        let rv = rustache::render_file("test_data/nested", HashBuilder::new());
+        match rv {
+            Err(FileError(msg)) => assert!(msg.contains("test_data/nested")),
+            _ => panic!("expected a FileError")
+        }
+    }
+
+    #[test]
+    fn test_render_file_explicit_escape_mode() {
+        let data = HashBuilder::new().insert_string("value", "a < b")
+                                     .set_escape_mode(EscapeMode::Html);
+
+        let rv = rustache::render_file("test_data/escape_by_extension.txt", data);
+
+        assert_eq!("<p>a &lt; b</p>".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_insert_rendered() {
+        let hero = HashBuilder::new().insert_string("name", "Anduin & Jaina");
+        let data = HashBuilder::new().insert_string("title", "<Heroes>")
+                                     .insert_rendered("card", "<b>{{ name }}</b>", &hero).unwrap();
+
+        let rv = rustache::render_string_from_hash("<h1>{{ title }}</h1>{{{ card }}}", &data);
+
+        assert_eq!("<h1>&lt;Heroes&gt;</h1><b>Anduin &amp; Jaina</b>".to_string(), rv.unwrap());
+    }
+
+    #[test]
+    fn test_compiled_template() {
+        let template = rustache::CompiledTemplate::new("<li>{{name}}{{#admin}} (admin){{/admin}}</li>").unwrap();
+
+        for i in 0..10 {
+            let data = HashBuilder::new().insert_string("name", format!("user{}", i))
+                                         .insert_bool("admin", i % 5 == 0);
+            let expected = if i % 5 == 0 {
+                format!("<li>user{} (admin)</li>", i)
+            } else {
+                format!("<li>user{}</li>", i)
+            };
+            assert_eq!(expected, template.render(&data).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_compiled_template_render_to() {
+        let template = rustache::CompiledTemplate::new("<p>{{name}}</p>").unwrap();
+        let mut outputs = vec![Vec::new(), Vec::new(), Vec::new()];
+
+        for (i, out) in outputs.iter_mut().enumerate() {
+            let data = HashBuilder::new().insert_string("name", format!("user{}", i));
+            template.render_to(out, &data).unwrap();
+        }
+
+        assert_eq!(b"<p>user0</p>".to_vec(), outputs[0]);
+        assert_eq!(b"<p>user1</p>".to_vec(), outputs[1]);
+        assert_eq!(b"<p>user2</p>".to_vec(), outputs[2]);
+    }
+
+    #[test]
+    fn test_render_to_buf() {
+        let data = HashBuilder::new().insert_string("name", "world");
+        let mut buf = [0u8; 16];
+
+        let len = rustache::render_to_buf("Hello, {{ name }}!", &data, &mut buf).unwrap();
+        assert_eq!(13, len);
+        assert_eq!(b"Hello, world!", &buf[..len]);
+
+        let mut small = [0u8; 8];
+        let rv = rustache::render_to_buf("Hello, {{ name }}!", &data, &mut small);
+        assert_eq!("Err(OutputLimitExceeded(more than 8 bytes, 8 written))".to_string(), format!("{:?}", rv));
+        assert_eq!(b"Hello, w", &small);
+    }
+
+    #[test]
+    fn test_render_string_from_hash() {
+        let data = HashBuilder::new().insert_string("name", "world");
+
+        assert_eq!("Hello, world!".to_string(), rustache::render_string_from_hash("Hello, {{ name }}!", &data).unwrap());
+        assert_eq!("".to_string(), rustache::render_string_from_hash("", &data).unwrap());
+    }
+
+    #[test]
+    fn test_render_json_numbers() {
+        let json = r#"{"count": 3, "big": 4000000000, "list": [1, 2.5]}"#.to_string();
+
+        let rv = rustache::render_text("{{count}} {{big}} {{#list}}{{.}};{{/list}}", json);
+        assert_eq!("3 4000000000 1;2.5;".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+
+        let rv = rustache::render_text("{{.}}", "[1]".to_string());
+        assert_eq!("Err(JsonError: \"Invalid JSON. Expected an object\")".to_string(), format!("{:?}", rv.map(|_| ())));
+    }
+
+    #[test]
+    fn test_render_json_null() {
+        let json = r#"{"name": "Ada", "user": {"name": null}, "list": [1, null, 2]}"#.to_string();
+
+        let rv = rustache::render_text("[{{#user}}{{name}}{{/user}}] {{#list}}({{.}}){{/list}}", json);
+        assert_eq!("[] (1)()(2)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_rows() {
+        let rows = vec![vec!["Ada".to_string(), "Lovelace".to_string()],
+                        vec!["Grace".to_string(), "Hopper".to_string()]];
+        let template = "<table>{{#rows}}<tr><td>{{first}}</td><td>{{last}}</td></tr>{{/rows}}</table>";
+
+        let rv = rustache::render_rows(template, &["first", "last"], &rows);
+
+        assert_eq!("<table><tr><td>Ada</td><td>Lovelace</td></tr><tr><td>Grace</td><td>Hopper</td></tr></table>".to_string(), rv.unwrap());
+    }
+
+    #[test]
+    fn test_render_cow_borrows_tagless_template() {
+        let data = HashBuilder::new().insert_string("name", "world");
+
+        match rustache::render_cow("Hello, world!\n", &data).unwrap() {
+            Cow::Borrowed(text) => assert_eq!("Hello, world!\n", text),
+            Cow::Owned(_) => panic!("expected a borrowed template")
+        }
+
+        match rustache::render_cow("Hello, {{name}}!", &data).unwrap() {
+            Cow::Owned(text) => assert_eq!("Hello, world!".to_string(), text),
+            Cow::Borrowed(_) => panic!("expected an owned render")
+        }
+
+        match rustache::render_cow("{{=<% %>=}}Hi", &data).unwrap() {
+            Cow::Owned(text) => assert_eq!("Hi".to_string(), text),
+            Cow::Borrowed(_) => panic!("expected an owned render")
+        }
+    }
+
+    #[test]
+    fn test_render_cow_tagless_template_post_processed() {
+        let data = HashBuilder::new().set_final_newline(FinalNewline::Ensure);
+        assert_eq!("abc\n", rustache::render_cow("abc", &data).unwrap());
+
+        let data = HashBuilder::new().set_html_minify(true);
+        assert_eq!("<p>a b</p>", rustache::render_cow("<p>a\n  b</p>", &data).unwrap());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_render_serde() {
+        let value = json!({
+            "name": "Ada",
+            "year": 1843,
+            "friends": [{ "name": "Charles" }, { "name": "Mary" }]
+        });
+
+        let rv = rustache::render_serde("{{name}} ({{year}}):{{#friends}} {{name}}{{/friends}}", &value);
+
+        assert_eq!("Ada (1843): Charles Mary".to_string(), rv.unwrap());
+        assert!(rustache::render_serde("{{name}}", &json!(["Ada"])).is_err());
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_render_file_with_front_matter() {
+        let data = HashBuilder::new().insert_string("site", "Hearthstone")
+                                     .insert_string("title", "Home");
+
+        let rv = rustache::render_file_with_front_matter("test_data/front_matter.html", data);
+
+        assert_eq!("<h1>Heroes &amp; Villains</h1> mage priest (3) Hearthstone\n".to_string(),
+                   String::from_utf8(rv.unwrap().unwrap()).unwrap());
+
+        let rv = rustache::render_file_with_front_matter("test_data/front_matter_bad.html", HashBuilder::new());
+        let msg = format!("{:?}", rv.err().unwrap());
+        assert!(msg.contains("front_matter_bad.html: front matter"));
+    }
+}
@@ -1,24 +1,210 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::fs::File;
 use std::fmt;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::io;
 use std::io::{Read,Write};
+use std::mem;
+use std::panic;
+use std::panic::AssertUnwindSafe;
 
 use compiler;
+use compiler::PartialArg;
 use parser;
 use parser::Node;
 use parser::Node::{Value, Static, Unescaped, Section, Part};
 use Data;
-use Data::{Strng, Bool, Integer, Float, Vector, Hash, Lambda};
+use OrderedMap;
+use Data::{Strng, Bool, Integer, Float, Vector, Hash, Lambda, DataLambda, Null};
 use build::HashBuilder;
 use std::collections::HashMap;
 
 use RustacheResult;
+use RustacheError;
 use RustacheError::TemplateErrorType;
 use self::TemplateError::*;
 
-pub struct Template {
-   partials_path: String
+pub struct Template<'t, 'h: 't> {
+   partials_path: String,
+   template_dir: String,
+   file_escape_mode: Option<EscapeMode>,
+   partials: Option<&'t HashMap<String, String>>,
+   assigned: Vec<(String, String)>,
+   strict_variables: bool,
+   strict_sections: bool,
+   escape_mode: EscapeMode,
+   escape_fn: Option<&'t (Fn(&str) -> String + 'h)>,
+   escape_slash: bool,
+   null_rendering: NullMode,
+   max_output_bytes: Option<usize>,
+   float_precision: Option<usize>,
+   escaped_entities: Vec<String>,
+   production: bool,
+   errors: Option<Vec<RustacheError>>,
+   on_tag: Option<&'t RefCell<&'h mut FnMut(&str, TagKind)>>,
+   transform: Option<&'t RefCell<&'h mut FnMut(&str) -> String>>,
+   catch_lambda_panics: bool,
+   truthiness: fn(&Data) -> bool,
+   empty_strings_falsy: bool,
+   partial_chain: Vec<(String, usize)>
+}
+
+/// Selects what a `{{ value }}` tag whose data is `Null` renders as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NullMode {
+    /// Render nothing (the default)
+    Empty,
+    /// Render the literal text `null`
+    Literal
+}
+
+/// Selects what happens to newlines at the end of the rendered output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FinalNewline {
+    /// Leave the output as rendered (the default)
+    Keep,
+    /// End the output with exactly one newline
+    Ensure,
+    /// Remove any newlines from the end of the output
+    Strip
+}
+
+/// The kind of tag passed to a tag hook, see `HashBuilder::set_on_tag`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TagKind {
+    /// An escaped `{{ value }}` tag
+    Variable,
+    /// An unescaped `{{& value }}` or `{{{ value }}}` tag
+    Unescaped,
+    /// A `{{# section }}` tag
+    Section,
+    /// A `{{^ section }}` tag
+    InvertedSection,
+    /// A `{{> partial }}` tag
+    Partial
+}
+
+/// Selects how the output of `{{ value }}` tags is escaped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EscapeMode {
+    /// Escape characters that are special in HTML (the default)
+    Html,
+    /// Backslash-escape characters that are special in Markdown
+    Markdown,
+    /// Escape characters that are special in XML
+    Xml,
+    /// Write values out as they are
+    Plain
+}
+
+// how deep partials may include one another.  loops build a fresh scope
+// for each item, so a cycle through a loop never revisits the same
+// context and needs this to stop it
+const MAX_PARTIAL_DEPTH: usize = 50;
+
+// the process-wide escape mode, stored as the index given by escape_mode_index
+static DEFAULT_ESCAPE_MODE: AtomicUsize = AtomicUsize::new(0);
+
+fn escape_mode_index(mode: EscapeMode) -> usize {
+    match mode {
+        EscapeMode::Html => 0,
+        EscapeMode::Markdown => 1,
+        EscapeMode::Xml => 2,
+        EscapeMode::Plain => 3
+    }
+}
+
+/// Set the escape mode used by renders whose data doesn't set one with
+/// `HashBuilder::set_escape_mode`.  This applies to every thread.
+///
+/// ```rust
+/// use rustache::EscapeMode;
+/// rustache::set_default_escape_mode(EscapeMode::Markdown);
+/// ```
+pub fn set_default_escape_mode(mode: EscapeMode) {
+    DEFAULT_ESCAPE_MODE.store(escape_mode_index(mode), Ordering::SeqCst);
+}
+
+/// Whether a section renders for the given data, unless the data sets its
+/// own test with `HashBuilder::set_truthiness`.  As in the spec, `false`,
+/// `null` and empty lists are falsy, and everything else is truthy.
+///
+/// ```rust
+/// use rustache::Data;
+/// assert_eq!(false, rustache::default_truthiness(&Data::Vector(vec![])));
+/// assert_eq!(true, rustache::default_truthiness(&Data::Integer(0)));
+/// ```
+pub fn default_truthiness(data: &Data) -> bool {
+    let mut rv = true;
+
+    match data {
+        // if the data is a bool, rv is just the bool value
+        &Bool(value) => { rv = value; },
+        &Null => { rv = false; },
+        &Vector(ref vec) => {
+            if vec.len() == 0 {
+                rv = false;
+            }
+        },
+        _ => { }
+    }
+
+    return rv;
+}
+
+// strings and numbers are the values a tag can write out directly
+fn is_scalar(data: &Data) -> bool {
+    match *data {
+        Strng(_) | Integer(_) | Float(_) => true,
+        _ => false
+    }
+}
+
+/// Get the escape mode set by `set_default_escape_mode`, HTML if never set
+pub fn default_escape_mode() -> EscapeMode {
+    match DEFAULT_ESCAPE_MODE.load(Ordering::SeqCst) {
+        1 => EscapeMode::Markdown,
+        2 => EscapeMode::Xml,
+        3 => EscapeMode::Plain,
+        _ => EscapeMode::Html
+    }
+}
+
+// the hash a section made by dot notation, like the a in {{ a.b }}, looks up
+// the rest of the name in.  the rest only resolves against that hash, not
+// any enclosing context.  sections written in the template have closing
+// text, dot notation ones don't.
+fn dotted_hash<'b, 'a>(data: &'b Data<'a>, close: &str) -> Option<&'b OrderedMap<Data<'a>>> {
+    match *data {
+        Hash(ref hash) if close.is_empty() => Some(hash),
+        _ => None
+    }
+}
+
+// copies data so it can be used in a new scope, like a partial with
+// arguments.  lambdas can't be copied, so they are left out.
+pub fn copy_data<'a>(data: &Data<'a>) -> Option<Data<'a>> {
+    match *data {
+        Strng(ref val) => Some(Strng(val.clone())),
+        Bool(val) => Some(Bool(val)),
+        Integer(val) => Some(Integer(val)),
+        Float(val) => Some(Float(val)),
+        Vector(ref list) => Some(Vector(list.iter().filter_map(copy_data).collect())),
+        Hash(ref hash) => {
+            let mut copy = OrderedMap::new();
+            for (key, val) in hash.iter() {
+                match copy_data(val) {
+                    Some(data) => { copy.insert(key.clone(), data); },
+                    None => { }
+                }
+            }
+            Some(Hash(copy))
+        },
+        Lambda(_) | DataLambda(_) => None,
+        Null => Some(Null)
+    }
 }
 
 pub enum TemplateError {
@@ -26,6 +212,186 @@ pub enum TemplateError {
     FileReadError(String),
     UnexpectedDataType(String),
     UnexpectedNodeType(String),
+    UndefinedVariable(String),
+    UndefinedSection(String),
+    OutputLimitExceeded(String),
+    InvalidTemplate(String),
+    LambdaPanicked(String),
+    PartialCycle(String),
+}
+
+// wraps the output stream to count the bytes written through it,
+// refusing any write that would go past the limit
+struct LimitedWriter<'w, W: Write + 'w> {
+    writer: &'w mut W,
+    written: usize,
+    limit: usize,
+    exceeded: bool
+}
+
+impl<'w, W: Write> Write for LimitedWriter<'w, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written + buf.len() > self.limit {
+            self.exceeded = true;
+            return Err(io::Error::new(io::ErrorKind::Other, "output limit exceeded"));
+        }
+        let written = try!(self.writer.write(buf));
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+// whether text starts with the given lowercase opening tag, e.g. "<pre"
+fn starts_with_tag(text: &str, tag: &str) -> bool {
+    let bytes = text.as_bytes();
+    if bytes.len() <= tag.len() {
+        return false;
+    }
+    let next = bytes[tag.len()];
+    let name_matches = bytes[..tag.len()].iter().zip(tag.bytes()).all(|(a, b)| a.to_ascii_lowercase() == b);
+
+    name_matches && (next == b'>' || next == b'/' || (next as char).is_whitespace())
+}
+
+// removes the newlines at the end of the output, then for Ensure adds
+// back one, using \r\n if that is what the last line ended with
+fn apply_final_newline(output: &mut String, policy: FinalNewline) {
+    if policy == FinalNewline::Keep {
+        return;
+    }
+    let mut ending = None;
+    loop {
+        if output.ends_with("\r\n") {
+            let len = output.len() - 2;
+            output.truncate(len);
+            ending = ending.or(Some("\r\n"));
+        } else if output.ends_with("\n") {
+            let len = output.len() - 1;
+            output.truncate(len);
+            ending = ending.or(Some("\n"));
+        } else {
+            break;
+        }
+    }
+    if policy == FinalNewline::Ensure {
+        output.push_str(ending.unwrap_or("\n"));
+    }
+}
+
+// where the lowercase needle first appears in text, ignoring ASCII case
+fn find_ignore_case(text: &str, needle: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let needle = needle.as_bytes();
+    if bytes.len() < needle.len() {
+        return None;
+    }
+    (0..bytes.len() - needle.len() + 1).find(|&start| {
+        bytes[start..start + needle.len()].iter().zip(needle.iter()).all(|(a, b)| a.to_ascii_lowercase() == *b)
+    })
+}
+
+// elements that are laid out as blocks, so whitespace next to their tags
+// doesn't show on the page
+const BLOCK_TAGS: &'static [&'static str] = &[
+    "address", "article", "aside", "blockquote", "body", "br", "dd", "div", "dl", "dt",
+    "fieldset", "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "head",
+    "header", "hr", "html", "li", "link", "main", "meta", "nav", "ol", "p", "pre",
+    "script", "section", "style", "table", "tbody", "td", "tfoot", "th", "thead", "title",
+    "tr", "ul"
+];
+
+// whether the opening or closing tag at the start of text is for a block
+// element, e.g. "<div class=x>" or "</p>"
+fn is_block_tag(text: &str) -> bool {
+    let name: String = text.chars().skip_while(|&c| c == '<' || c == '/')
+                           .take_while(|c| c.is_alphanumeric()).collect();
+    BLOCK_TAGS.contains(&&name.to_ascii_lowercase()[..])
+}
+
+// removes HTML comments, removes whitespace at either end and next to the
+// tags of block elements, and collapses any other run of whitespace to a
+// single space.  the contents of <pre>, <textarea>,
+// <script> and <style> are kept exactly as they are.
+fn minify_html(input: &str) -> String {
+    let mut rv = String::new();
+    let mut rest = input;
+
+    while !rest.is_empty() {
+        if rest.starts_with("<!--") {
+            rest = match rest.find("-->") {
+                Some(end) => &rest[end + 3..],
+                None => ""
+            };
+            continue;
+        }
+
+        let mut preserved = false;
+        for &(open, close) in [("<pre", "</pre>"), ("<textarea", "</textarea>"),
+                               ("<script", "</script>"), ("<style", "</style>")].iter() {
+            if starts_with_tag(rest, open) {
+                let end = match find_ignore_case(rest, close) {
+                    Some(pos) => pos + close.len(),
+                    None => rest.len()
+                };
+                rv.push_str(&rest[..end]);
+                rest = &rest[end..];
+                preserved = true;
+                break;
+            }
+        }
+        if preserved {
+            continue;
+        }
+
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            // a comment inside a run of whitespace goes with it, so the
+            // whitespace either side becomes at most one space
+            let mut after = rest;
+            loop {
+                after = &after[after.find(|c: char| !c.is_whitespace()).unwrap_or(after.len())..];
+                if !after.starts_with("<!--") {
+                    break;
+                }
+                after = match after.find("-->") {
+                    Some(end) => &after[end + 3..],
+                    None => ""
+                };
+            }
+            let at_edge = rv.is_empty() || after.is_empty();
+            let block_before = rv.ends_with('>') && rv.rfind('<').map_or(false, |pos| is_block_tag(&rv[pos..]));
+            let block_after = after.starts_with('<') && is_block_tag(after);
+            if !at_edge && !block_before && !block_after {
+                rv.push(' ');
+            }
+            rest = after;
+        } else {
+            rv.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    rv
+}
+
+// adds the indentation to the start of every line that isn't empty.  a
+// line holding only the \r of a \r\n line ending counts as empty.
+fn indent_lines(text: &str, indentation: &str) -> String {
+    let mut rv = String::new();
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            rv.push('\n');
+        }
+        if !line.is_empty() && line != "\r" {
+            rv.push_str(indentation);
+        }
+        rv.push_str(line);
+    }
+    rv
 }
 
 impl fmt::Debug for TemplateError {
@@ -35,15 +401,94 @@ impl fmt::Debug for TemplateError {
             &FileReadError(ref val)     => write!(f, "FileReadError({})", val),
             &UnexpectedDataType(ref val) => write!(f, "UnexpectedDataType({})", val),
             &UnexpectedNodeType(ref val) => write!(f, "UnexpectedNodeType({})", val),
+            &UndefinedVariable(ref val) => write!(f, "UndefinedVariable({})", val),
+            &UndefinedSection(ref val)  => write!(f, "UndefinedSection({})", val),
+            &OutputLimitExceeded(ref val) => write!(f, "OutputLimitExceeded({})", val),
+            &InvalidTemplate(ref val)   => write!(f, "InvalidTemplate({})", val),
+            &LambdaPanicked(ref val)    => write!(f, "LambdaPanicked({})", val),
+            &PartialCycle(ref val)      => write!(f, "PartialCycle({})", val),
         }
     }
 }
 
-impl Template {
-    pub fn new() -> Template {
+impl<'t, 'h> Template<'t, 'h> {
+    pub fn new() -> Template<'t, 'h> {
         Template {
-            partials_path: String::new()
+            partials_path: String::new(),
+            template_dir: String::new(),
+            file_escape_mode: None,
+            partials: None,
+            assigned: Vec::new(),
+            strict_variables: false,
+            strict_sections: false,
+            escape_mode: EscapeMode::Html,
+            escape_fn: None,
+            escape_slash: false,
+            null_rendering: NullMode::Empty,
+            max_output_bytes: None,
+            float_precision: None,
+            escaped_entities: Vec::new(),
+            production: false,
+            errors: None,
+            on_tag: None,
+            transform: None,
+            catch_lambda_panics: false,
+            truthiness: default_truthiness,
+            empty_strings_falsy: false,
+            partial_chain: Vec::new()
+        }
+    }
+
+    // the template file being rendered.  partials are found next to it
+    // when the data doesn't set a partials path, and its extension picks
+    // the escape mode when the data doesn't set one.
+    pub fn set_template_file(&mut self, path: &Path) {
+        self.template_dir.truncate(0);
+        match path.parent().and_then(|dir| dir.to_str()) {
+            Some(dir) => self.template_dir.push_str(dir),
+            None => {}
         }
+        self.file_escape_mode = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("html") | Some("htm") => Some(EscapeMode::Html),
+            Some("xml") => Some(EscapeMode::Xml),
+            Some("txt") => Some(EscapeMode::Plain),
+            _ => None
+        };
+    }
+
+    // partials registered by name, looked up instead of partial files
+    pub fn set_partials(&mut self, partials: &'t HashMap<String, String>) {
+        self.partials = Some(partials);
+    }
+
+    // the values assigned by {{#set name}} tags so far, in the order
+    // they were rendered
+    pub fn take_assigned(&mut self) -> Vec<(String, String)> {
+        mem::replace(&mut self.assigned, Vec::new())
+    }
+
+    fn assign(&mut self, key: &str, rendered: &[u8]) {
+        let value = String::from_utf8_lossy(rendered).into_owned();
+        self.assigned.push((key.to_string(), value));
+    }
+
+    // tell the tag hook, if there is one, which tag is about to render
+    fn notify_tag(&self, node: &Node) {
+        let hook = match self.on_tag {
+            Some(hook) => hook,
+            None => return
+        };
+        let (name, kind) = match *node {
+            Section(compiler::DEV_ONLY, _, _, _, _) => return,
+            Value(key, _) => (key, TagKind::Variable),
+            Unescaped(key, _) => (key, TagKind::Unescaped),
+            Section(key, _, false, _, _) => (key, TagKind::Section),
+            Section(key, _, true, _, _) => (key, TagKind::InvertedSection),
+            Part(contents, _, _) => (compiler::partial_name(contents), TagKind::Partial),
+            Static(_) => return
+        };
+        let f = &mut *hook.borrow_mut();
+        (*f)(name, kind);
     }
 
     // utility method to write out rendered template with error handling
@@ -52,7 +497,7 @@ impl Template {
                                   data: &String,
                                   errstr: &str) -> RustacheResult<()> {
         let mut rv: RustacheResult<()> = Ok(());
-        let status = writer.write_fmt(format_args!("{}", &data[..]));
+        let status = writer.write_all(data.as_bytes());
         match status {
             Err(err) => {
                 let msg = format!("{}: {}", err, errstr);
@@ -64,15 +509,92 @@ impl Template {
         return rv;
     }
 
+    // when collecting errors, the error is recorded and a placeholder showing
+    // it is written in place of the tag, escaped like a value, so rendering
+    // can carry on.
+    // otherwise the error is returned and rendering stops.
+    fn render_error<W: Write>(&mut self, err: TemplateError, writer: &mut W) -> RustacheResult<()> {
+        let placeholder = self.escape(&format!("[{:?}]", err));
+        match self.errors {
+            Some(ref mut errors) => errors.push(TemplateErrorType(err)),
+            None => return Err(TemplateErrorType(err))
+        }
+
+        return self.write_to_stream(writer, &placeholder, "render: error placeholder");
+    }
+
+    // the error for a section whose data is missing, if it is one.  A
+    // dotted variable like {{a.b}} compiles to sections with no closing
+    // tag, so strict_variables covers those rather than strict_sections.
+    fn undefined_section(&self, key: String, close: &str) -> Option<TemplateError> {
+        if close.is_empty() {
+            if self.strict_variables { Some(UndefinedVariable(key)) } else { None }
+        } else if self.strict_sections {
+            Some(UndefinedSection(key))
+        } else {
+            None
+        }
+    }
+
+    // method to escape value tags according to the configured escape mode
+    fn escape(&self, input: &str) -> Box<String> {
+        match self.escape_fn {
+            Some(escape) => return Box::new(escape(input)),
+            None => {}
+        }
+        match self.escape_mode {
+            EscapeMode::Html => self.escape_html(input),
+            EscapeMode::Markdown => self.escape_markdown(input),
+            EscapeMode::Xml => self.escape_xml(input),
+            EscapeMode::Plain => Box::new(input.to_string())
+        }
+    }
+
+    // method to escape the five characters XML predefines entities for
+    fn escape_xml(&self, input: &str) -> Box<String> {
+        let mut rv = Box::new(String::new());
+        for c in input.chars() {
+            match c {
+                '<'  => { rv.push_str("&lt;"); }
+                '>'  => { rv.push_str("&gt;"); }
+                '&'  => { rv.push_str("&amp;"); }
+                '"'  => { rv.push_str("&quot;"); }
+                '\'' => { rv.push_str("&apos;"); }
+                _    => { rv.push(c); }
+            }
+        }
+        rv
+    }
+
+    // method to escape Markdown formatting characters with a backslash
+    fn escape_markdown(&self, input: &str) -> Box<String> {
+        let mut rv = Box::new(String::new());
+        for c in input.chars() {
+            match c {
+                '\\' | '`' | '*' | '_' | '{' | '}' | '[' | ']' | '(' | ')' |
+                '#' | '+' | '-' | '!' | '|' | '<' | '>' => {
+                    rv.push('\\');
+                    rv.push(c);
+                }
+                _ => { rv.push(c); }
+            }
+        }
+        rv
+    }
+
     // method to escape HTML for default value tags
     fn escape_html(&self, input: &str) -> Box<String> {
         let mut rv = Box::new(String::new());
-        for c in input.chars() {
+        for (i, c) in input.char_indices() {
             match c {
                 '<'  => { rv.push_str("&lt;"); }
                 '>'  => { rv.push_str("&gt;"); }
+                // an & starting one of the configured entities is already escaped
+                '&' if self.escaped_entities.iter().any(|entity| input[i..].starts_with(&entity[..])) => { rv.push(c); }
                 '&'  => { rv.push_str("&amp;"); }
                 '"'  => { rv.push_str("&quot;"); }
+                '\'' => { rv.push_str("&#39;"); }
+                '/' if self.escape_slash => { rv.push_str("&#x2F;"); }
                 _    => { rv.push(c); }
             }
         }
@@ -89,8 +611,43 @@ impl Template {
     fn look_up_section_data<'a, 'b>(&self,
                                     key: &String,
                                     sections: &Vec<String>,
-                                    datastore: &'b HashMap<String, Data<'a>>) -> Option<&'b Data<'a>> {
+                                    datastore: &'b OrderedMap<Data<'a>>) -> Option<&'b Data<'a>> {
         let mut rv = None;
+
+        // a vector at the top level for any section is the section's data
+        for section in sections.iter() {
+            match datastore.get(section) {
+                Some(data @ &Vector(_)) => return Some(data),
+                _ => { }
+            }
+        }
+
+        // once we've assembled the vector of hashes to look through
+        // we iterate through it looking for the data
+        for hash in self.section_hashes(sections, datastore).iter() {
+
+            rv = hash.get(key);
+            if rv.is_some() {
+                break;
+            }
+        }
+
+        // last but not least, check the top level if we didn't find anything
+        if rv.is_none() {
+            rv = datastore.get(key);
+        }
+
+        return rv;
+    }
+
+    // sections:  the nested sections we are currently in, see look_up_section_data
+    // datastore: the hash of the data the sections are found in
+    //
+    // the hashes for the nested sections, innermost first, which is the
+    // order look_up_section_data searches them in.
+    fn section_hashes<'a, 'b>(&self,
+                              sections: &Vec<String>,
+                              datastore: &'b OrderedMap<Data<'a>>) -> Vec<&'b OrderedMap<Data<'a>>> {
         let mut hashes = Vec::new();
         let mut hash = datastore;
 
@@ -147,54 +704,107 @@ impl Template {
                         Hash(ref h) => {
                             hashes.insert(0, h);
                         },
-                        Vector(_) => {
-                            return Some(data);
-                        }
                         _ => { }
                     }
                 }
             }
         }
 
-        // once we've assembled the vector of hashes to look through
-        // we iterate through it looking for the data
-        for hash in hashes.iter() {
+        return hashes;
+    }
 
-            rv = hash.get(key);
-            if rv.is_some() {
-                break;
+    // key:   the key we're looking for
+    // stack: the hashes of the sections enclosing the current data, outermost first
+    //
+    // a name that isn't in the current data is looked for in each enclosing
+    // context in turn, from the innermost out.  so inside a list of hashes,
+    // {{company}} still finds a company set next to the list.
+    fn look_up_stack<'a, 'b>(&self,
+                             key: &str,
+                             stack: &[&'b OrderedMap<Data<'a>>]) -> Option<&'b Data<'a>> {
+        for hash in stack.iter().rev() {
+            match hash.get(key) {
+                Some(data) => return Some(data),
+                None => { }
             }
         }
 
-        // last but not least, check the top level if we didn't find anything
+        return None;
+    }
+
+    // key:       a section key which may be dotted, e.g. a.b.c
+    // sections:  the nested sections we are currently in, see look_up_section_data
+    // datastore: the hash of the data to search for key in
+    // stack:     the enclosing contexts, see look_up_stack
+    //
+    // the first part of a dotted key is looked up like any other section key,
+    // the rest of the parts have to be found in the hash found for the part
+    // before them.  if any part along the way is missing, there is no data.
+    fn look_up_dotted_data<'a, 'b>(&self,
+                                   key: &String,
+                                   sections: &Vec<String>,
+                                   datastore: &'b OrderedMap<Data<'a>>,
+                                   stack: &[&'b OrderedMap<Data<'a>>]) -> Option<&'b Data<'a>> {
+        let mut parts = key.split('.');
+        let first = parts.next().unwrap_or("").to_string();
+        let mut rv = self.look_up_section_data(&first, sections, datastore);
         if rv.is_none() {
-            rv = datastore.get(key);
+            rv = self.look_up_stack(&first, stack);
+        }
+
+        for part in parts {
+            rv = match rv {
+                Some(&Hash(ref h)) => h.get(part),
+                _ => None
+            };
         }
 
         return rv;
     }
 
+    // name: the key the lambda was found under
+    // call: calls the lambda
+    //
+    // lambdas are user code, so when asked to, a panic in one is caught
+    // and turned into an error naming the lambda instead of unwinding
+    // through the render.
+    fn call_lambda<T, F: FnOnce() -> T>(&self, name: &str, call: F) -> Result<T, TemplateError> {
+        if !self.catch_lambda_panics {
+            return Ok(call());
+        }
+        match panic::catch_unwind(AssertUnwindSafe(call)) {
+            Ok(val) => Ok(val),
+            Err(_) => Err(LambdaPanicked(format!("lambda {} panicked", name)))
+        }
+    }
+
     fn handle_unescaped_lambda_interpolation<W: Write>(&mut self,
+                                                        name: &str,
                                                         f: &mut FnMut(String) -> String,
-                                                        data: &HashMap<String, Data>,
+                                                        data: &OrderedMap<Data>,
                                                         raw: String,
                                                         writer: &mut W) -> RustacheResult<()> {
-        let val = (*f)(raw);
-        let mut tokens = compiler::create_tokens(&val[..]);
-        let nodes = parser::parse_nodes(&mut tokens);
+        let val = match self.call_lambda(name, || (*f)(raw)) {
+            Ok(val) => val,
+            Err(err) => return self.render_error(err, writer)
+        };
+        let nodes = parser::compile(&val[..]);
 
         return self.render(writer, data, &nodes);
     }
 
     fn handle_escaped_lambda_interpolation<W: Write>(&mut self,
+                                                      name: &str,
                                                       f: &mut FnMut(String) -> String,
-                                                      data: &HashMap<String, Data>,
+                                                      data: &OrderedMap<Data>,
                                                       raw: String,
                                                       writer: &mut W) -> RustacheResult<()> {
-        let val = (*f)(raw);
-        let value = self.escape_html(&val[..]);
-        let mut tokens = compiler::create_tokens(&value[..]);
-        let nodes = parser::parse_nodes(&mut tokens);
+        let val = match self.call_lambda(name, || (*f)(raw)) {
+            Ok(val) => val,
+            Err(err) => return self.render_error(err, writer)
+        };
+        let value = self.escape(&val[..]);
+        let nodes = parser::compile(&value[..]);
 
         return self.render(writer, data, &nodes);
     }
@@ -213,17 +823,25 @@ impl Template {
                                         node: &Node,
                                         data: &Data,
                                         key: String,
-                                        datastore: &HashMap<String, Data>,
+                                        datastore: &OrderedMap<Data>,
                                         writer: &mut W) -> RustacheResult<()>{
         let mut rv = Ok(());
         let mut tmp: String = String::new();
         match *data {
             // simple value-for-tag exchange, write out the string
             Strng(ref val) => {
+                let transformed;
+                let val = match self.transform {
+                    Some(f) => {
+                        transformed = (&mut *f.borrow_mut())(&val[..]);
+                        &transformed
+                    },
+                    None => val
+                };
                 match *node {
                     Unescaped(_,_) => tmp = tmp + val,
-                    Value(_,_) => tmp = *self.escape_html(&val[..]),
-                    _ => return Err(TemplateErrorType(UnexpectedNodeType(format!("{:?}", node))))
+                    Value(_,_) => tmp = *self.escape(&val[..]),
+                    _ => return self.render_error(UnexpectedNodeType(format!("{:?}", node)), writer)
                 }
                 rv = self.write_to_stream(writer, &tmp, "render: unescaped node string fail");
             },
@@ -242,38 +860,53 @@ impl Template {
             },
             // if the data is a float, convert it to a string and write that
             Float(ref val) => {
-                tmp = tmp + &val.to_string();
+                match self.float_precision {
+                    Some(places) => tmp = tmp + &format!("{:.*}", places, val),
+                    None => tmp = tmp + &val.to_string()
+                }
                 rv = self.write_to_stream(writer, &tmp, "render: unescaped node float");
             },
-            // TODO: this one doesn't quite make sense.  i don't think we need it.
+            // a list can't be written out as text
             Vector(ref list) => {
-                for item in list.iter() {
-                    rv = self.handle_unescaped_or_value_node(node, item, key.to_string(), datastore, writer);
-                    match rv {
-                        Ok(_) => { },
-                        _ => { return rv; }
-                    }
-                }
+                let msg = format!("expecting text for {:?}, found vector data {:?}", node, list);
+                rv = self.render_error(UnexpectedDataType(msg), writer);
             },
-            // TODO: this one doesn't quite make sense.  i don't think we need it.
+            // nor can a hash
             Hash(ref hash) => {
-                if hash.contains_key(&key) {
-                    let ref tmp = hash[&key];
-                    rv = self.handle_unescaped_or_value_node(node, tmp, key.to_string(), datastore, writer);
-                    match rv {
-                        Ok(_) => { },
-                        _ => { return rv; }
-                    }
-                }
+                let msg = format!("expecting text for {:?}, found hash data {:?}", node, hash);
+                rv = self.render_error(UnexpectedDataType(msg), writer);
             },
             // if we have a lambda for the data, the return value of the
             // lambda is what we substitute for the tag
             Lambda(ref f) => {
                 let raw = "".to_string();
                 match *node {
-                    Unescaped(_,_) => rv = self.handle_unescaped_lambda_interpolation(&mut *f.borrow_mut(), datastore, raw, writer),
-                    Value(_,_) => rv = self.handle_escaped_lambda_interpolation(&mut *f.borrow_mut(), datastore, raw, writer),
-                    _ => return Err(TemplateErrorType(UnexpectedNodeType(format!("{:?}", node))))
+                    Unescaped(name,_) => rv = self.handle_unescaped_lambda_interpolation(name, &mut *f.borrow_mut(), datastore, raw, writer),
+                    Value(name,_) => rv = self.handle_escaped_lambda_interpolation(name, &mut *f.borrow_mut(), datastore, raw, writer),
+                    _ => return self.render_error(UnexpectedNodeType(format!("{:?}", node)), writer)
+                }
+            },
+            // a data lambda is called with an empty string, like a plain
+            // lambda, and whatever data it returns is written out instead
+            DataLambda(ref f) => {
+                let name = match *node {
+                    Value(name, _) | Unescaped(name, _) => name,
+                    _ => ""
+                };
+                let value = match self.call_lambda(name, || (&mut *f.borrow_mut())("".to_string())) {
+                    Ok(value) => value,
+                    Err(err) => return self.render_error(err, writer)
+                };
+                rv = self.handle_unescaped_or_value_node(node, &value, key.to_string(), datastore, writer);
+            },
+            // null renders as nothing, unless asked to spell it out
+            Null => {
+                match self.null_rendering {
+                    NullMode::Empty => {},
+                    NullMode::Literal => {
+                        tmp.push_str("null");
+                        rv = self.write_to_stream(writer, &tmp, "render: unescaped node null");
+                    }
                 }
             }
         }
@@ -288,9 +921,10 @@ impl Template {
     // inverted nodes only contain static text to render and are only rendered
     // if the data in the template data for the tag name is "falsy"
     //
-    fn handle_inverted_node<W:Write>(&mut self,
+    fn handle_inverted_node<'a, 'b, W:Write>(&mut self,
                                       nodes: &Vec<Node>,
-                                      datastore: &HashMap<String, Data>,
+                                      datastore: &'b OrderedMap<Data<'a>>,
+                                      stack: &[&'b OrderedMap<Data<'a>>],
                                       writer: &mut W) -> RustacheResult<()> {
         println!("handle inverted node: nodes: {:?}, datastore: {:?}", nodes, datastore);
         let mut rv = Ok(());
@@ -299,30 +933,21 @@ impl Template {
                 Static(key) => {
                     rv = self.write_to_stream(writer, &key.to_string(), "render: inverted node static");
                 },
+                // the section data is falsy, so values come from the enclosing scope
+                Value(_, _) | Unescaped(_, _) => {
+                    rv = self.handle_node(node, datastore, stack, writer);
+                },
                 // TODO: this one doesn't quite make sense.  i don't think we need it.
-                Part(filename, _) => {
-                    rv = self.handle_partial_file_node(filename, datastore, writer);
+                Part(contents, raw, indentation) => {
+                    self.notify_tag(node);
+                    rv = self.handle_partial_file_node(contents, raw, indentation, datastore, stack, writer);
                 },
-                Section(ref key, ref children, ref inverted, _, _) => {
-                    let tmp = key.to_string();
-                    let truthy = if datastore.contains_key(&tmp) {
-                        self.is_section_data_true(&datastore[&tmp])
-                    } else {
-                        false
-                    };
-                    match (truthy, *inverted) {
-                        (true, true) => {},
-                        (false, false) => {},
-                        (true, false) => {
-                            let ref val = datastore[&tmp];
-                            let mut sections = vec![tmp.clone()];
-                            rv = self.handle_section_node(children, &tmp, val, datastore, &mut sections, writer);
-                        },
-                        (false, true) => {
-                            rv = self.handle_inverted_node(children, datastore, writer);
-                        }
-                    }
+                Section(_, _, _, _, _) => {
+                    rv = self.handle_node(node, datastore, stack, writer);
                 }
+            }
+            match rv {
+                Err(_) => { return rv; },
                 _ => { }
             }
         }
@@ -333,35 +958,90 @@ impl Template {
     // nodes:     the section's children
     // data:      data from section key from HashBuilder store
     // datastore: HashBuilder data
+    // stack:     the contexts enclosing datastore, outermost first
     // writer:    io stream
-    fn handle_section_node<W: Write>(&mut self,
+    fn handle_section_node<'a, 'b, W: Write>(&mut self,
                                       nodes: &Vec<Node>,
-                                      sectionkey: &String,
-                                      data: &Data,
-                                      datastore: &HashMap<String,Data>,
+                                      data: &'b Data<'a>,
+                                      datastore: &'b OrderedMap<Data<'a>>,
+                                      stack: &[&'b OrderedMap<Data<'a>>],
                                       sections: &mut Vec<String>,
                                       writer: &mut W) -> RustacheResult<()> {
         let mut rv = Ok(());
         // there's a special case if the section tag data was a lambda
         // if so, the lambda is used to generate the values for the tag inside the section
+        let name = sections.last().cloned().unwrap_or(String::new());
         match data {
+          // the lambda's output is rendered again as a template, with every
+          // enclosing section's data still in scope
           &Lambda(ref f) => {
             let raw = self.get_section_text(nodes);
-            return self.handle_unescaped_lambda_interpolation(&mut *f.borrow_mut(), datastore, *raw, writer);
+            let val = match self.call_lambda(&name, || (&mut *f.borrow_mut())(*raw)) {
+              Ok(val) => val,
+              Err(err) => return self.render_error(err, writer)
+            };
+            let nodes = parser::compile(&val[..]);
+            let mut inner = self.push_contexts(datastore, stack, sections);
+            let innermost = inner.pop().unwrap_or(datastore);
+            return self.render_in_stack(writer, innermost, &inner, &nodes);
+          },
+          // a data lambda gets the raw section text too, and the section is
+          // rendered over the data it returns
+          &DataLambda(ref f) => {
+            let raw = self.get_section_text(nodes);
+            let value = match self.call_lambda(&name, || (&mut *f.borrow_mut())(*raw)) {
+              Ok(value) => value,
+              Err(err) => return self.render_error(err, writer)
+            };
+            if !self.is_section_data_true(&value) {
+              return rv;
+            }
+            return self.handle_section_node(nodes, &value, datastore, stack, sections, writer);
           },
           &Vector(ref v) => {
-            for d in v.iter() {
+            // each hash in the list becomes the innermost context, inside a
+            // scope holding the loop's {{@index}}, {{@first}} and {{@last}}
+            let inner = self.push_contexts(datastore, stack, sections);
+            for (index, d) in v.iter().enumerate() {
+                let mut scope = OrderedMap::new();
+                scope.insert("@index".to_string(), Integer(index as i32));
+                scope.insert("@first".to_string(), Bool(index == 0));
+                scope.insert("@last".to_string(), Bool(index + 1 == v.len()));
+                let mut looped = inner.clone();
+                looped.push(&scope);
                 for node in nodes.iter() {
                     match d {
                         &Hash(ref h) => {
-                            rv = self.handle_node(node, h, writer);
+                            rv = self.handle_node(node, h, &looped, writer);
                         },
-                        &Strng(ref val) => return Err(TemplateErrorType(UnexpectedDataType(format!("{}", val)))),
-                        &Bool(ref val) => return Err(TemplateErrorType(UnexpectedDataType(format!("{}", val)))),
-                        &Integer(ref val) => return Err(TemplateErrorType(UnexpectedDataType(format!("{}", val)))),
-                        &Float(ref val) => return Err(TemplateErrorType(UnexpectedDataType(format!("{}", val)))),
-                        &Vector(ref val) => return Err(TemplateErrorType(UnexpectedDataType(format!("{:?}", val)))),
-                        &Lambda(_) => return Err(TemplateErrorType(UnexpectedDataType("lambda".to_string()))),
+                        // any other element is the implicit iterator {{.}}, other
+                        // tags still come from the enclosing data
+                        _ => {
+                            match *node {
+                                Value(".", _) | Unescaped(".", _) => {
+                                    self.notify_tag(node);
+                                    rv = self.handle_unescaped_or_value_node(node, d, ".".to_string(), datastore, writer);
+                                },
+                                // a {{#.}} section is over the element itself, like a
+                                // row in a vector of vectors
+                                Section(".", ref children, inverted, _, _) => {
+                                    self.notify_tag(node);
+                                    let truthy = self.is_section_data_true(d);
+                                    if truthy && !inverted {
+                                        rv = self.handle_section_node(children, d, &scope, &inner, &mut Vec::new(), writer);
+                                    } else if !truthy && inverted {
+                                        rv = self.handle_inverted_node(children, &scope, &inner, writer);
+                                    }
+                                },
+                                _ => {
+                                    rv = self.handle_node(node, &scope, &inner, writer);
+                                }
+                            }
+                        }
+                    }
+                    match rv {
+                        Err(_) => { return rv; },
+                        _ => { }
                     }
                 }
             }
@@ -373,142 +1053,402 @@ impl Template {
         // in a section tag, there are child tags to fill out,
         // we need to iterate through each one
         for node in nodes.iter() {
+          self.notify_tag(node);
           match *node {
+                // in a section over a single string or number, the implicit
+                // iterator is that value
+                Value(".", _) | Unescaped(".", _) if is_scalar(data) => {
+                  rv = self.handle_unescaped_or_value_node(node, data, ".".to_string(), datastore, writer);
+                }
                 // unescaped is simple, just look up the data in the
                 // special way sections need to and handle the node
                 Unescaped(key, _)  => {
                   let tmpkey = key.to_string();
-                  let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore);
+                  let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore)
+                                    .or_else(|| self.look_up_stack(key, stack));
                   if tmpdata.is_some() {
                     rv = self.handle_unescaped_or_value_node(node, tmpdata.unwrap(), key.to_string(), datastore, writer);
+                  } else if self.strict_variables {
+                    rv = self.render_error(UndefinedVariable(tmpkey), writer);
                   }
                 }
                 // unescaped is simple, just look up the data in the
                 // special way sections need to and handle the node
                 Value(key, _) => {
                   let tmpkey = key.to_string();
-                  let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore);
+                  let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore)
+                                    .or_else(|| self.look_up_stack(key, stack));
                   if tmpdata.is_some() {
                     rv = self.handle_unescaped_or_value_node(node, tmpdata.unwrap(), key.to_string(), datastore, writer);
+                  } else if self.strict_variables {
+                    rv = self.render_error(UndefinedVariable(tmpkey), writer);
                   }
                 }
                 // most simple, just write the static data out, nothing to replace
                 Static(key) => {
                   rv = self.write_to_stream(writer, &key.to_string(), "render: section node static");
                 }
+                // dev-only regions stay in the current section's scope
+                Section(compiler::DEV_ONLY, ref children, _, _, _) => {
+                  if !self.production {
+                    rv = self.handle_section_node(children, data, datastore, stack, sections, writer);
+                  }
+                }
+                // set tags capture their rendered body instead of writing it
+                Section(key, ref children, false, _, _) if compiler::set_target(key).is_some() => {
+                  let mut buffer: Vec<u8> = Vec::new();
+                  rv = self.handle_section_node(children, data, datastore, stack, sections, &mut buffer);
+                  self.assign(compiler::set_target(key).unwrap(), &buffer);
+                }
+                // repeat tags render their body a fixed number of times
+                Section(key, ref children, false, _, _) if compiler::repeat_count(key).is_some() => {
+                  let count = compiler::repeat_count(key).unwrap();
+                  rv = self.handle_repeat_node(children, count, datastore, stack, sections, writer);
+                }
+                // each tags render their body for every entry of a hash
+                Section(key, ref children, false, _, ref close) if compiler::each_target(key).is_some() => {
+                  let target = compiler::each_target(key).unwrap();
+                  rv = self.handle_each_node(children, target, close, datastore, stack, sections, writer);
+                }
                 // sections are special and may be inverted
-                Section(ref key, ref children, ref inverted, _, _) => {
+                Section(ref key, ref children, ref inverted, _, ref close) => {
                   match inverted {
                         // A normal, not inverted tag is more complicated and may recurse
                         // we need to save what sections we have been in, so the data
                         // lookup can happen correctly.  Data lookup is special for sections.
                         &false => {
-                          let foo = sectionkey;
-                          println!("{}", foo);
                           let tmpkey = key.to_string();
-                          sections.push(tmpkey.clone());
-                          let tmpdata = self.look_up_section_data(&tmpkey, sections, datastore);
+                          let tmpdata = self.look_up_dotted_data(&tmpkey, sections, datastore, stack);
+                          for part in tmpkey.split('.') {
+                              sections.push(part.to_string());
+                          }
                           if tmpdata.is_some() {
-                            rv = self.handle_section_node(children, &tmpkey, tmpdata.unwrap(), datastore, sections, writer);
+                            match self.collection_size(tmpdata.unwrap(), children, close) {
+                              Some(size) => {
+                                rv = self.write_to_stream(writer, &size.to_string(), "render: section node size");
+                              },
+                              // falsy data like Bool(false) means the children render zero times
+                              None => {
+                                if self.is_section_data_true(tmpdata.unwrap()) {
+                                  rv = match dotted_hash(tmpdata.unwrap(), close) {
+                                    Some(hash) => self.render_in_stack(writer, hash, &[], children),
+                                    None => self.handle_section_node(children, tmpdata.unwrap(), datastore, stack, sections, writer)
+                                  };
+                                }
+                              }
+                            }
+                          } else {
+                            match self.undefined_section(tmpkey, close) {
+                              Some(err) => rv = self.render_error(err, writer),
+                              None => {}
+                            }
                           }
                         },
-                        // inverted only has internal static text, so is easy to handle
+                        // inverted sections only render when their data is missing or falsy
                         &true => {
-                          rv = self.handle_inverted_node(children, datastore, writer);
+                          let tmpkey = key.to_string();
+                          let tmpdata = self.look_up_dotted_data(&tmpkey, sections, datastore, stack);
+                          if tmpdata.is_none() && self.strict_sections {
+                            rv = self.render_error(UndefinedSection(tmpkey), writer);
+                          } else {
+                            let truthy = match tmpdata {
+                              Some(data) => self.is_section_data_true(data),
+                              None => false
+                            };
+                            if !truthy {
+                              rv = self.handle_inverted_node(children, datastore, stack, writer);
+                            }
+                          }
                         }
                       }
                     },
                 // if it's a partial, we have a file to read in and render
-                Part(path, _) => {
-                  rv = self.handle_partial_file_node(path, datastore, writer);
+                Part(contents, raw, indentation) => {
+                  rv = self.handle_partial_file_node(contents, raw, indentation, datastore, stack, writer);
                 }
             }
+            match rv {
+                Err(_) => { return rv; },
+                _ => { }
+            }
         }
 
         return rv;
     }
 
-    // section data is considered false in a few cases:
-    // there is no data for the key in the data hashmap
-    // the data is a bool with a value of false
-    // the data is an empty vector
-    fn is_section_data_true(&self, data: &Data) -> bool {
-        let mut rv = true;
-
-        match data {
-            // if the data is a bool, rv is just the bool value
-            &Bool(value) => { rv = value; },
-            &Vector(ref vec) => {
-                if vec.len() == 0 {
-                    rv = false;
-                }
-            },
-            _ => { }
+    // datastore: the data for the innermost context
+    // stack:     the contexts enclosing datastore, outermost first
+    // sections:  the nested sections we are currently in
+    //
+    // the stack to use for a new innermost context, with the data and the
+    // hashes for the sections we're in pushed on to it.
+    fn push_contexts<'a, 'b>(&self,
+                             datastore: &'b OrderedMap<Data<'a>>,
+                             stack: &[&'b OrderedMap<Data<'a>>],
+                             sections: &Vec<String>) -> Vec<&'b OrderedMap<Data<'a>>> {
+        let mut inner = stack.to_vec();
+        inner.push(datastore);
+        for hash in self.section_hashes(sections, datastore).into_iter().rev() {
+            inner.push(hash);
         }
 
-        return rv;
+        return inner;
     }
 
-    // children: a vector of nodes representing the template text
-    //           found between the section tags
-    //
-    // in the case of values for a section being lambdas, we need to pass
-    // the raw text of the inside of the section tags to the lambda.
-    // we store the raw text of each tag in the tag enum itself,
-    // so we iterate through the children of the section, pulling out
-    // the raw text and creating a string of it to pass to the lambda.
+    // nodes: the section's children
+    // count: how many times to render them
     //
-    fn get_section_text(&self, children: &Vec<Node>) -> Box<String> {
-        let mut temp = Box::new(String::new());
-        for child in children.iter() {
-            match child {
-                &Static(text) => temp.push_str(text),
-                &Value(_, ref text) => temp.push_str(&text[..]),
-                &Section(_, ref children, _, ref open, ref close) => {
-                    let rv = self.get_section_text(children);
-                    temp.push_str(&open[..]);
-                    temp.push_str(&rv[..]);
-                    temp.push_str(&close[..]);
-                },
-                &Unescaped(_, ref text) => temp.push_str(&text[..]),
-                &Part(_, text) => temp.push_str(text)
+    // each time through, the children are rendered with {{@index}} set to
+    // the number of times they've been rendered so far, and {{@first}} and
+    // {{@last}} set as they are for a vector.
+    fn handle_repeat_node<'a, 'b, W: Write>(&mut self,
+                                             nodes: &Vec<Node>,
+                                             count: usize,
+                                             datastore: &'b OrderedMap<Data<'a>>,
+                                             stack: &[&'b OrderedMap<Data<'a>>],
+                                             sections: &Vec<String>,
+                                             writer: &mut W) -> RustacheResult<()> {
+        let mut rv = Ok(());
+        let inner = self.push_contexts(datastore, stack, sections);
+        for index in 0..count {
+            let mut scope = OrderedMap::new();
+            scope.insert("@index".to_string(), Integer(index as i32));
+            scope.insert("@first".to_string(), Bool(index == 0));
+            scope.insert("@last".to_string(), Bool(index + 1 == count));
+            for node in nodes.iter() {
+                rv = self.handle_node(node, &scope, &inner, writer);
+                match rv {
+                    Err(_) => { return rv; },
+                    _ => { }
+                }
             }
         }
-        temp
+
+        return rv;
     }
 
+    // nodes:  the section's children
+    // target: the name of the hash to go through
+    // close:  the section's closing tag text
     //
-    // filename:  the filename of the partial template to include,
-    //            a.k.a the value inside the tag
-    // datastore: all the template data
+    // the children are rendered once for each entry of the hash, in the
+    // order its keys were inserted, with {{@key}} and {{@value}} set to the
+    // entry, and {{@index}}, {{@first}} and {{@last}} set as they are for a
+    // vector.  data that isn't a hash renders nothing.
+    fn handle_each_node<'a, 'b, W: Write>(&mut self,
+                                           nodes: &Vec<Node>,
+                                           target: &str,
+                                           close: &str,
+                                           datastore: &'b OrderedMap<Data<'a>>,
+                                           stack: &[&'b OrderedMap<Data<'a>>],
+                                           sections: &Vec<String>,
+                                           writer: &mut W) -> RustacheResult<()> {
+        let mut rv = Ok(());
+        let hash = match self.look_up_dotted_data(&target.to_string(), sections, datastore, stack) {
+            Some(&Hash(ref hash)) => hash,
+            Some(_) => return rv,
+            None => {
+                return match self.undefined_section(target.to_string(), close) {
+                    Some(err) => self.render_error(err, writer),
+                    None => rv
+                };
+            }
+        };
+
+        let inner = self.push_contexts(datastore, stack, sections);
+        for (index, (key, value)) in hash.iter().enumerate() {
+            let mut scope = OrderedMap::new();
+            scope.insert("@key".to_string(), Strng(key.clone()));
+            match copy_data(value) {
+                Some(value) => { scope.insert("@value".to_string(), value); },
+                None => { }
+            }
+            scope.insert("@index".to_string(), Integer(index as i32));
+            scope.insert("@first".to_string(), Bool(index == 0));
+            scope.insert("@last".to_string(), Bool(index + 1 == hash.len()));
+            for node in nodes.iter() {
+                rv = self.handle_node(node, &scope, &inner, writer);
+                match rv {
+                    Err(_) => { return rv; },
+                    _ => { }
+                }
+            }
+        }
+
+        return rv;
+    }
+
+    // whether a section renders for its data, see HashBuilder::set_truthiness
+    // and HashBuilder::set_empty_strings_falsy
+    fn is_section_data_true(&self, data: &Data) -> bool {
+        match *data {
+            Strng(ref val) if val.is_empty() && self.empty_strings_falsy => false,
+            _ => (self.truthiness)(data)
+        }
+    }
+
+    // data:     the data for a section
+    // children: the section's children
+    // close:    the section's closing tag text
+    //
+    // dot notation like {{ items.size }} is parsed into a section around a
+    // single `size` or `length` value tag, with no closing text, see
+    // parser::handle_dot_notation.  when the section data is a vector or
+    // hash that doesn't have such a key itself, the tag stands for the
+    // number of entries in the collection.
+    //
+    fn collection_size(&self, data: &Data, children: &Vec<Node>, close: &str) -> Option<usize> {
+        if children.len() != 1 || !close.is_empty() {
+            return None;
+        }
+        let name = match children[0] {
+            Value(name, _) | Unescaped(name, _) => name,
+            _ => return None
+        };
+        if name != "size" && name != "length" {
+            return None;
+        }
+
+        let name = name.to_string();
+        match *data {
+            Vector(ref list) => {
+                let shadowed = list.iter().any(|item| match *item {
+                    Hash(ref h) => h.contains_key(&name),
+                    _ => false
+                });
+                if shadowed { None } else { Some(list.len()) }
+            },
+            Hash(ref hash) => {
+                if hash.contains_key(&name) { None } else { Some(hash.len()) }
+            },
+            _ => None
+        }
+    }
+
+    // children: a vector of nodes representing the template text
+    //           found between the section tags
+    //
+    // in the case of values for a section being lambdas, we need to pass
+    // the raw text of the inside of the section tags to the lambda.
+    // we store the raw text of each tag in the tag enum itself,
+    // so we iterate through the children of the section, pulling out
+    // the raw text and creating a string of it to pass to the lambda.
+    //
+    fn get_section_text(&self, children: &Vec<Node>) -> Box<String> {
+        let mut temp = Box::new(String::new());
+        for child in children.iter() {
+            match child {
+                &Static(text) => temp.push_str(text),
+                &Value(_, ref text) => temp.push_str(&text[..]),
+                &Section(_, ref children, _, ref open, ref close) => {
+                    let rv = self.get_section_text(children);
+                    temp.push_str(&open[..]);
+                    temp.push_str(&rv[..]);
+                    temp.push_str(&close[..]);
+                },
+                &Unescaped(_, ref text) => temp.push_str(&text[..]),
+                &Part(_, text, _) => temp.push_str(text)
+            }
+        }
+        temp
+    }
+
+    //
+    // contents:  the value inside the tag, the filename of the partial
+    //            template to include and any key=value arguments
+    // raw:       the whole partial tag
+    // datastore: all the template data
     // writer:    the io stream to write the rendered template out to
     //
     // in the mustache spec, it says parials are rendered at runtime,
     // so we call render in this method.  datastore and writer are taken
     // in as parameters because we have to do this
     //
+    // arguments like {{> partial key1=a key2="b" }} are pushed as a new
+    // innermost context, just for the partial.
+    //
+    // a partial that includes itself again with the same data, directly or
+    // through other partials, would never finish, so that is an error
+    // naming the partials in the cycle.  including itself for nested data,
+    // like a tree, is fine.  partials nested past MAX_PARTIAL_DEPTH are
+    // also an error, since a cycle through a loop sees new data each time.
+    //
     // TODO: throw error if partials file doesn't exist, if file read fails
     //
-    fn handle_partial_file_node<W: Write>(&mut self,
-                                           filename: &str,
-                                           datastore: &HashMap<String, Data>,
+    fn handle_partial_file_node<'a, 'b, W: Write>(&mut self,
+                                           contents: &str,
+                                           raw: &str,
+                                           indentation: &str,
+                                           datastore: &'b OrderedMap<Data<'a>>,
+                                           stack: &[&'b OrderedMap<Data<'a>>],
                                            writer: &mut W) -> RustacheResult<()> {
+        let filename = compiler::partial_name(contents);
+        let context = datastore as *const OrderedMap<Data<'a>> as usize;
+        let visited = self.partial_chain.iter().position(|&(ref name, ctx)| name == filename && ctx == context);
+        match visited {
+            Some(start) => {
+                let mut names: Vec<&str> = self.partial_chain[start..].iter().map(|&(ref name, _)| &name[..]).collect();
+                names.push(filename);
+                let msg = format!("partial cycle: {}", names.join(" -> "));
+                return self.render_error(PartialCycle(msg), writer);
+            },
+            None => {}
+        }
+        if self.partial_chain.len() >= MAX_PARTIAL_DEPTH {
+            let msg = format!("partial cycle: {} nested more than {} deep", filename, MAX_PARTIAL_DEPTH);
+            return self.render_error(PartialCycle(msg), writer);
+        }
+
+        self.partial_chain.push((filename.to_string(), context));
+        let rv = self.include_partial(contents, raw, indentation, datastore, stack, writer);
+        self.partial_chain.pop();
+
+        return rv;
+    }
+
+    // finds a partial by name, in the registered partials or on the
+    // filesystem, and renders it
+    fn include_partial<'a, 'b, W: Write>(&mut self,
+                                          tag: &str,
+                                          raw: &str,
+                                          indentation: &str,
+                                          datastore: &'b OrderedMap<Data<'a>>,
+                                          stack: &[&'b OrderedMap<Data<'a>>],
+                                          writer: &mut W) -> RustacheResult<()> {
+        let filename = compiler::partial_name(tag);
         let mut rv: RustacheResult<()> = Ok(());;
-        let path = Path::new(&self.partials_path.clone()).join(filename);
+        // registered partials never touch the filesystem
+        match self.partials {
+            Some(partials) => {
+                return match partials.get(filename) {
+                    Some(contents) => self.render_partial(&contents[..], tag, raw, indentation, datastore, stack, writer),
+                    None => rv
+                };
+            },
+            None => {}
+        }
+        // partial names written with either separator work on every platform
+        let mut path = PathBuf::from(&self.partials_path);
+        for part in filename.split(|c| c == '/' || c == '\\') {
+            if !part.is_empty() {
+                path.push(part);
+            }
+        }
+        // a bare partial name means a .mustache file of that name
+        if path.extension().is_none() && !fs::metadata(&path).is_ok() {
+            path.set_extension("mustache");
+        }
         if fs::metadata(&path).is_ok() {
 
             let mut contents = String::new();
             let file = File::open(&path).and_then( |ref mut f| f.read_to_string(&mut contents) );
             match file {
                 Ok(_) => {
-                    let mut tokens = compiler::create_tokens(&contents[..]);
-                    let nodes = parser::parse_nodes(&mut tokens);
-
-                    rv = self.render(writer, datastore, &nodes);
+                    rv = self.render_partial(&contents[..], tag, raw, indentation, datastore, stack, writer);
                 },
                 Err(err) => {
                     let msg = format!("{}: {}", err, filename);
-                    rv = Err(TemplateErrorType(FileReadError(msg)));
+                    rv = self.render_error(FileReadError(msg), writer);
                 }
             }
         } // if the file is not found, it's supposed to fail silently
@@ -516,24 +1456,90 @@ impl Template {
         return rv;
     }
 
-    fn handle_node<W: Write>(&mut self, node: &Node, datastore: &HashMap<String, Data>, writer: &mut W)  -> RustacheResult<()> {
+    // compiles a partial's contents and renders it with the current data,
+    // plus any arguments given in the partial tag.  A standalone partial's
+    // indentation is added to the start of each of its lines.
+    fn render_partial<'a, 'b, W: Write>(&mut self,
+                                contents: &str,
+                                tag: &str,
+                                raw: &str,
+                                indentation: &str,
+                                datastore: &'b OrderedMap<Data<'a>>,
+                                stack: &[&'b OrderedMap<Data<'a>>],
+                                writer: &mut W) -> RustacheResult<()> {
+        let indented;
+        let contents = if indentation.is_empty() {
+            contents
+        } else {
+            indented = indent_lines(contents, indentation);
+            &indented[..]
+        };
+        let nodes = match parser::compile_checked(contents) {
+            Ok(nodes) => nodes,
+            Err(msg) => return self.render_error(InvalidTemplate(format!("{}: {}", raw.trim(), msg)), writer)
+        };
+
+        let args = compiler::partial_args(tag);
+        if args.is_empty() {
+            return self.render_in_stack(writer, datastore, stack, &nodes);
+        }
+
+        let mut scope = OrderedMap::new();
+        for (key, arg) in args.into_iter() {
+            let data = match arg {
+                PartialArg::Literal(val) => Some(Strng(val.to_string())),
+                PartialArg::Key(name) => {
+                    match self.look_up_dotted_data(&name.to_string(), &Vec::new(), datastore, stack) {
+                        Some(val) => copy_data(val),
+                        None => None
+                    }
+                }
+            };
+            match data {
+                Some(data) => { scope.insert(key.to_string(), data); },
+                None => { }
+            }
+        }
+        let mut inner = stack.to_vec();
+        inner.push(datastore);
+        return self.render_in_stack(writer, &scope, &inner, &nodes);
+    }
+
+    // node:      the node to render
+    // datastore: the data for the innermost context
+    // stack:     the contexts enclosing datastore, outermost first
+    // writer:    io stream
+    fn handle_node<'a, 'b, W: Write>(&mut self,
+                                      node: &Node,
+                                      datastore: &'b OrderedMap<Data<'a>>,
+                                      stack: &[&'b OrderedMap<Data<'a>>],
+                                      writer: &mut W)  -> RustacheResult<()> {
         let mut rv = Ok(());
+        self.notify_tag(node);
 
         match *node {
             Unescaped(key, _)  => {
                 let tmp = key.to_string();
-                if datastore.contains_key(&tmp) {
-                    let ref val = datastore[&tmp];
-                    rv = self.handle_unescaped_or_value_node(node, val, "".to_string(), datastore, writer);
+                match datastore.get(&tmp).or_else(|| self.look_up_stack(key, stack)) {
+                    Some(val) => {
+                        rv = self.handle_unescaped_or_value_node(node, val, "".to_string(), datastore, writer);
+                    },
+                    None => if self.strict_variables {
+                        rv = self.render_error(UndefinedVariable(tmp), writer);
+                    }
                 }
             }
             // value nodes contain tags who's data gets HTML escaped
             // when it gets written out
             Value(key, _) => {
                 let tmp = key.to_string();
-                if datastore.contains_key(&tmp) {
-                    let ref val = datastore[&tmp];
-                    rv = self.handle_unescaped_or_value_node(node, val, "".to_string(), datastore, writer);
+                match datastore.get(&tmp).or_else(|| self.look_up_stack(key, stack)) {
+                    Some(val) => {
+                        rv = self.handle_unescaped_or_value_node(node, val, "".to_string(), datastore, writer);
+                    },
+                    None => if self.strict_variables {
+                        rv = self.render_error(UndefinedVariable(tmp), writer);
+                    }
                 }
             }
             // static nodes are the test in the template that doesn't get modified,
@@ -541,6 +1547,43 @@ impl Template {
             Static(key) => {
                 rv = self.write_to_stream(writer, &key.to_string(), "render: static");
             }
+            // dev-only regions render their children as if the region
+            // wasn't there, and are dropped entirely in production renders
+            Section(compiler::DEV_ONLY, ref children, _, _, _) => {
+                if !self.production {
+                    for child in children.iter() {
+                        rv = self.handle_node(child, datastore, stack, writer);
+                        match rv {
+                            Err(_) => { return rv; },
+                            _ => { }
+                        }
+                    }
+                }
+            }
+            // a {{#set name}} section renders its body into the assignments
+            // handed back by render_with_context, writing nothing out
+            Section(key, ref children, false, _, _) if compiler::set_target(key).is_some() => {
+                let mut buffer: Vec<u8> = Vec::new();
+                for child in children.iter() {
+                    rv = self.handle_node(child, datastore, stack, &mut buffer);
+                    match rv {
+                        Err(_) => { return rv; },
+                        _ => { }
+                    }
+                }
+                self.assign(compiler::set_target(key).unwrap(), &buffer);
+            }
+            // a {{#repeat 3}} section renders its body that many times
+            Section(key, ref children, false, _, _) if compiler::repeat_count(key).is_some() => {
+                let count = compiler::repeat_count(key).unwrap();
+                rv = self.handle_repeat_node(children, count, datastore, stack, &Vec::new(), writer);
+            }
+            // a {{#each name}} section renders its body for every entry of
+            // the hash, in the order the keys were inserted
+            Section(key, ref children, false, _, ref close) if compiler::each_target(key).is_some() => {
+                let target = compiler::each_target(key).unwrap();
+                rv = self.handle_each_node(children, target, close, datastore, stack, &Vec::new(), writer);
+            }
             // sections come in two kinds, normal and inverted
             //
             // inverted are if the tag data is not there, the Static between it
@@ -548,30 +1591,46 @@ impl Template {
             //
             // normal section tags enclose a bit of html that will get repeated
             // for each element found in it's data
-            Section(ref key, ref children, ref inverted, _, _) => {
+            Section(ref key, ref children, ref inverted, _, ref close) => {
                 let tmp = key.to_string();
-                let truthy = if datastore.contains_key(&tmp) {
-                    self.is_section_data_true(&datastore[&tmp])
-                } else {
-                    false
+                // dotted section keys like a.b.c are looked up a part at a time
+                let data = self.look_up_dotted_data(&tmp, &Vec::new(), datastore, stack);
+                if data.is_none() {
+                    match self.undefined_section(tmp.clone(), close) {
+                        Some(err) => return self.render_error(err, writer),
+                        None => {}
+                    }
+                }
+                if !*inverted && data.is_some() {
+                    match self.collection_size(data.unwrap(), children, close) {
+                        Some(size) => return self.write_to_stream(writer, &size.to_string(), "render: size"),
+                        None => {}
+                    }
+                }
+                let truthy = match data {
+                    Some(val) => self.is_section_data_true(val),
+                    None => false
                 };
                 match (truthy, *inverted) {
                     (true, true) => {},
                     (false, false) => {},
                     (true, false) => {
-                        let ref val = datastore[&tmp];
-                        let mut sections = vec![tmp.clone()];
-                        rv = self.handle_section_node(children, &tmp, val, datastore, &mut sections, writer);
+                        let val = data.unwrap();
+                        let mut sections = tmp.split('.').map(|part| part.to_string()).collect();
+                        rv = match dotted_hash(val, close) {
+                            Some(hash) => self.render_in_stack(writer, hash, &[], children),
+                            None => self.handle_section_node(children, val, datastore, stack, &mut sections, writer)
+                        };
                     },
                     (false, true) => {
-                        rv = self.handle_inverted_node(children, datastore, writer);
+                        rv = self.handle_inverted_node(children, datastore, stack, writer);
                     }
                 }
             }
             // partials include external template files and compile and process them
             // at runtime, inserting them into the document at the point the tag is found
-            Part(name, _) => {
-                rv = self.handle_partial_file_node(name, datastore, writer);
+            Part(contents, raw, indentation) => {
+                rv = self.handle_partial_file_node(contents, raw, indentation, datastore, stack, writer);
             }
         }
 
@@ -582,15 +1641,25 @@ impl Template {
     // parser: the parser object that has the parsed nodes, see src/parse.js
     pub fn render<W: Write>(&mut self,
                              writer: &mut W,
-                             data: &HashMap<String, Data>,
+                             data: &OrderedMap<Data>,
                              nodes: &Vec<Node>) -> RustacheResult<()> {
+        return self.render_in_stack(writer, data, &[], nodes);
+    }
+
+    // renders nodes with data as the innermost context, like a partial
+    // included from inside a section
+    fn render_in_stack<'a, 'b, W: Write>(&mut self,
+                                          writer: &mut W,
+                                          data: &'b OrderedMap<Data<'a>>,
+                                          stack: &[&'b OrderedMap<Data<'a>>],
+                                          nodes: &Vec<Node>) -> RustacheResult<()> {
         let mut rv = Ok(());
 
         // nodes are what the template file is parsed into
         // we have to iterate through each one and handle it as
         // the kind of node it is
         for node in nodes.iter() {
-            rv = self.handle_node(node, data, writer);
+            rv = self.handle_node(node, data, stack, writer);
             match rv {
                 Err(_) => { return rv; },
                 _ => { }
@@ -604,14 +1673,89 @@ impl Template {
     // main entry point to Template
     pub fn render_data<W: Write>(&mut self,
                                   writer: &mut W,
-                                  datastore: &HashBuilder,
+                                  datastore: &'t HashBuilder<'h>,
                                   nodes: &Vec<Node>) -> RustacheResult<()> {
         // we need to hang on to the partials path internally,
         // if there is one, for class methods to use.
+        // without one, partials are found next to the template file
         self.partials_path.truncate(0);
-        self.partials_path.push_str(datastore.partials_path);
+        if datastore.partials_path.is_empty() {
+            self.partials_path.push_str(&self.template_dir);
+        } else {
+            self.partials_path.push_str(datastore.partials_path);
+        }
+        self.strict_variables = datastore.strict_variables;
+        self.strict_sections = datastore.strict_sections;
+        self.escape_mode = match (datastore.escape_mode, self.file_escape_mode) {
+            (Some(mode), _) => mode,
+            (None, Some(mode)) => mode,
+            (None, None) => default_escape_mode()
+        };
+        self.escape_fn = datastore.escape_fn.as_ref().map(|escape| &**escape);
+        self.escape_slash = datastore.escape_slash;
+        self.null_rendering = datastore.null_rendering;
+        self.max_output_bytes = datastore.max_output_bytes;
+        self.float_precision = datastore.float_precision;
+        self.escaped_entities = datastore.escaped_entities.clone();
+        self.production = datastore.production;
+        self.on_tag = datastore.on_tag.as_ref();
+        self.transform = datastore.transform.as_ref();
+        self.catch_lambda_panics = datastore.catch_lambda_panics;
+        self.truthiness = datastore.truthiness;
+        self.empty_strings_falsy = datastore.empty_strings_falsy;
+
+        // minifying and fixing up the end need the whole output,
+        // so render into a buffer first
+        if datastore.html_minify || datastore.final_newline != FinalNewline::Keep {
+            let mut buffer: Vec<u8> = Vec::new();
+            try!(self.render_limited(&mut buffer, &datastore.data, nodes));
+            let mut output = String::from_utf8_lossy(&buffer).into_owned();
+            if datastore.html_minify {
+                output = minify_html(&output);
+            }
+            apply_final_newline(&mut output, datastore.final_newline);
+            return self.write_to_stream(writer, &output, "render: buffered output");
+        }
+
+        return self.render_limited(writer, &datastore.data, nodes);
+    }
+
+    // with an output limit, everything goes through a counting writer
+    // and the first write past the limit stops the render
+    fn render_limited<W: Write>(&mut self,
+                                writer: &mut W,
+                                data: &OrderedMap<Data>,
+                                nodes: &Vec<Node>) -> RustacheResult<()> {
+        match self.max_output_bytes {
+            Some(limit) => {
+                let mut limited = LimitedWriter { writer: writer, written: 0, limit: limit, exceeded: false };
+                let rv = self.render(&mut limited, data, nodes);
+                if limited.exceeded {
+                    let msg = format!("more than {} bytes", limit);
+                    return Err(TemplateErrorType(OutputLimitExceeded(msg)));
+                }
+                return rv;
+            },
+            None => return self.render(writer, data, nodes)
+        }
+    }
 
-        return self.render(writer, &datastore.data, nodes);
+    // renders as much as possible, collecting errors instead of stopping at
+    // the first one.  errors that stop the render, like a failed write, are
+    // collected last.
+    pub fn render_data_collect_errors<W: Write>(&mut self,
+                                                writer: &mut W,
+                                                datastore: &'t HashBuilder<'h>,
+                                                nodes: &Vec<Node>) -> Vec<RustacheError> {
+        self.errors = Some(Vec::new());
+        let rv = self.render_data(writer, datastore, nodes);
+        let mut errors = self.errors.take().unwrap_or(Vec::new());
+        match rv {
+            Err(err) => errors.push(err),
+            Ok(_) => { }
+        }
+
+        return errors;
     }
 
 }
@@ -623,6 +1767,7 @@ mod template_tests {
 
     use std::fs::File;
     use std::path::Path;
+    use std::io;
     use std::io::Write;
     use std::str;
 
@@ -633,9 +1778,12 @@ mod template_tests {
     use parser::Node::{Value, Static, Unescaped, Section, Part};
     use rustache;
     use compiler;
-    use template::Template;
+    use template::{Template, EscapeMode, NullMode, FinalNewline, TagKind, default_truthiness};
+    use template::TemplateError::{UnexpectedDataType, LambdaPanicked};
+    use RustacheError::TemplateErrorType;
     use build::{HashBuilder};
-    use Data::{Strng};
+    use Data;
+    use Data::{Strng, Integer, Vector};
 
     #[test]
     fn test_look_up_section_data() {
@@ -700,7 +1848,7 @@ mod template_tests {
     #[test]
     fn test_escape_html() {
         let s1 = "a < b > c & d \"spam\"\'";
-        let a1 = "a &lt; b &gt; c &amp; d &quot;spam&quot;'";
+        let a1 = "a &lt; b &gt; c &amp; d &quot;spam&quot;&#39;";
         let s2 = "1<2 <b>hello</b>";
         let a2 = "1&lt;2 &lt;b&gt;hello&lt;/b&gt;";
 
@@ -722,175 +1870,784 @@ mod template_tests {
     }
 
     #[test]
-    fn test_section_tag_iteration() {
-        let mut w = MemStream::new();
-        let template = "{{#repo}}<b>{{name}}</b>{{/repo}}";
-        let tokens = compiler::create_tokens(template);
-        let nodes = parser::parse_nodes(&tokens);
-        let data = HashBuilder::new().insert_vector("repo", |v| {
-                                        v.push_hash(|h| { h.insert_string("name", "resque") })
-                                        .push_hash(|h| { h.insert_string("name", "hub") })
-                                        .push_hash(|h| { h.insert_string("name", "rip") })
-                                    });
+    fn test_escaped_entities() {
+        let nodes: Vec<Node> = vec![Value("value", "{{ value }}".to_string())];
+        let data = HashBuilder::new().insert_string("value", "fish&nbsp;&amp; chips & <peas>")
+                                     .set_escaped_entities(&["&nbsp;"]);
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
-        match rv { _ => {} }
+        let mut w = MemStream::new();
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
 
-        assert_eq!("<b>resque</b><b>hub</b><b>rip</b>".to_string(), String::from_utf8(w.unwrap()).unwrap())
+        assert_eq!("fish&nbsp;&amp;amp; chips &amp; &lt;peas&gt;", str::from_utf8(w.as_slice()).unwrap());
     }
 
     #[test]
-    fn test_not_escape_html() {
-        let s = "1<2 <b>hello</b>";
+    fn test_escape_markdown() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Unescaped("value", "{{ value }}".to_string())];
-        let data = HashBuilder::new().insert_string("value", s);
+        let nodes: Vec<Node> = vec![Value("value", "{{ value }}".to_string()), Unescaped("value", "{{& value }}".to_string())];
+        let data = HashBuilder::new().insert_string("value", "*bold* [link]")
+                                     .set_escape_mode(EscapeMode::Markdown);
 
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
 
-        assert_eq!(s, str::from_utf8(w.as_slice()).unwrap());
+        assert_eq!("\\*bold\\* \\[link\\]*bold* [link]", str::from_utf8(w.as_slice()).unwrap());
     }
 
     #[test]
-    fn test_render_to_io_stream() {
+    fn test_null_rendering() {
+        let nodes: Vec<Node> = vec![Static("["), Value("value", "{{ value }}".to_string()), Static("]")];
+
         let mut w = MemStream::new();
-        let data = HashBuilder::new().insert_string("value1", "The heading");
-        let nodes: Vec<Node> = vec![Static("<h1>"), Value("value1", "{{ value1 }}".to_string()), Static("</h1>")];
+        let data = HashBuilder::new().insert_null("value");
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+        assert_eq!("[]", str::from_utf8(w.as_slice()).unwrap());
 
+        let mut w = MemStream::new();
+        let data = HashBuilder::new().insert_null("value")
+                                     .set_null_rendering(NullMode::Literal);
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
+        assert_eq!("[null]", str::from_utf8(w.as_slice()).unwrap());
+    }
 
-        assert_eq!("<h1>The heading</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    #[test]
+    fn test_null_sections() {
+        let data = HashBuilder::new().insert_null("nickname")
+                                     .insert_vector("aliases", |v| { v.push_null().push_string("Varian") });
+
+        assert_eq!("[]", render_str("[{{nickname}}{{&nickname}}]", &data));
+        assert_eq!("", render_str("{{#nickname}}called {{nickname}}{{/nickname}}", &data));
+        assert_eq!("no nickname", render_str("{{^nickname}}no nickname{{/nickname}}", &data));
+        assert_eq!("<><Varian>", render_str("{{#aliases}}<{{.}}>{{/aliases}}", &data));
     }
 
     #[test]
-    fn test_unescaped_node_correct_bool_false_data() {
-        let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Static("<h1>"), Unescaped("value1", "{{& value1 }}".to_string()), Static("</h1>")];
-        let data = HashBuilder::new().insert_bool("value1", false);
+    fn test_optional_values_in_sections() {
+        let template = "{{#nickname}}aka {{nickname}}{{/nickname}}{{^nickname}}no nickname{{/nickname}}, \
+                        {{#age}}{{age}} years{{/age}}{{^age}}age unknown{{/age}}";
 
-        let rv = Template::new().render_data(&mut w, &data, &nodes);
-        match rv { _ => {} }
+        let data = HashBuilder::new().insert_option("nickname", Some("Varian")).insert_option("age", Some(40));
+        assert_eq!("aka Varian, 40 years", render_str(template, &data));
 
-        assert_eq!("<h1>false</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        let data = HashBuilder::new().insert_option("nickname", None::<String>).insert_option("age", None::<i32>);
+        assert_eq!("no nickname, age unknown", render_str(template, &data));
     }
 
     #[test]
-    fn test_unescaped_node_correct_bool_true_data() {
+    fn test_vector_size() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Static("<h1>"), Unescaped("value1", "{{& value1 }}".to_string()), Static("</h1>")];
-        let data = HashBuilder::new().insert_bool("value1", true);
+        let tokens = compiler::create_tokens("You have {{ messages.size }} messages and {{ empty.length }} alerts");
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_vector("messages", |v| {
+                                        v.push_string("hi").push_string("hello").push_string("hey")
+                                    })
+                                    .insert_vector("empty", |v| { v });
 
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
 
-        assert_eq!("<h1>true</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
-    }
+        assert_eq!("You have 3 messages and 0 alerts".to_string(), String::from_utf8(w.unwrap()).unwrap());
 
-    #[test]
-    fn test_section_value_string_data() {
+        // only dot notation counts, a written section still looks up size
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Section("value1", vec![Value("value", "{{ value }}".to_string())], false, "{{# value1 }}".to_string(), "{{/ value1 }}".to_string())];
-        let data = HashBuilder::new()
-            .insert_hash("value1", |builder| {
-                builder.insert_string("value", "<Section Value>")
-            });
+        let tokens = compiler::create_tokens("{{#messages}}{{size}}{{/messages}}");
+        let nodes = parser::parse_nodes(&tokens);
+        let data = data.insert_int("size", 7);
 
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
 
-        assert_eq!("&lt;Section Value&gt;".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        assert_eq!("777".to_string(), String::from_utf8(w.unwrap()).unwrap());
     }
 
     #[test]
-    fn test_section_multiple_value_string_data() {
+    fn test_hash_size() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Section("names", vec![Value("name", "{{ name }}".to_string())], false, "{{# names }}".to_string(), "{{/ names }}".to_string())];
-        let data = HashBuilder::new()
-            .insert_hash("names", |builder| {
-                builder.insert_vector("name", |builder| {
-                    builder
-                        .push_string("tom")
-                        .push_string("robert")
-                        .push_string("joe")
-                })
-            });
+        let tokens = compiler::create_tokens("{{ user.size }} fields, {{#page}}{{ user.length }}{{/page}}, {{ box.size }}");
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_hash("user", |h| {
+                                        h.insert_string("name", "Ada").insert_string("lang", "en")
+                                    })
+                                    .insert_hash("page", |h| { h })
+                                    .insert_hash("box", |h| { h.insert_string("size", "large") });
 
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
 
-        assert_eq!("tomrobertjoe".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        assert_eq!("2 fields, 2, large".to_string(), String::from_utf8(w.unwrap()).unwrap());
     }
 
-    // #[test]
-    // fn test_excessively_nested_data() {
-    //     let mut w = MemStream::new();
-    //     let nodes: Vec<Node> = vec![Section("hr", vec![Section("people", vec![Value("name", "{{ name }}".to_string())], false, "{{# people }}".to_string(), "{{/ people }}".to_string())], false, "{{# hr }}".to_string(), "{{/ hr }}".to_string())];
-    //     let data = HashBuilder::new()
-    //         .insert_hash("hr", |builder| {
-    //             builder.insert_hash("people", |builder| {
-    //                 builder
-    //                     .insert_vector("name", |builder| {
-    //                         builder
-    //                             .push_string("tom")
-    //                             .push_string("robert")
-    //                             .push_string("joe")
-    //                 })
-    //             })
-    //         });
-
-    //     let rv = Template::new().render_data(&mut w, &data, &nodes);
-    //     assert_eq!("tomrobertjoe".to_string(), String::from_utf8(w.unwrap()).unwrap());
-    // }
-
     #[test]
-    fn test_unescaped_node_lambda_data() {
+    fn test_section_tag_iteration() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Static("<h1>"), Unescaped("func1", "{{& func1 }}".to_string()), Static("</h1>")];
-        let mut f = |_| { "heading".to_string() };
-        let data = HashBuilder::new().insert_lambda("func1", &mut f);
+        let template = "{{#repo}}<b>{{name}}</b>{{/repo}}";
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_vector("repo", |v| {
+                                        v.push_hash(|h| { h.insert_string("name", "resque") })
+                                        .push_hash(|h| { h.insert_string("name", "hub") })
+                                        .push_hash(|h| { h.insert_string("name", "rip") })
+                                    });
 
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
 
-        assert_eq!("<h1>heading</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        assert_eq!("<b>resque</b><b>hub</b><b>rip</b>".to_string(), String::from_utf8(w.unwrap()).unwrap())
     }
 
     #[test]
-    fn test_value_node_lambda_data() {
+    fn test_section_vector_of_hashes() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Static("<h1>"), Value("func1", "{{ func1 }}".to_string()), Static("</h1>")];
-        let mut f = |_| { "heading".to_string() };
-        let data = HashBuilder::new().insert_lambda("func1", &mut f);
+        let tokens = compiler::create_tokens("<ul>{{#users}}<li>{{name}} ({{role}})</li>{{/users}}</ul>");
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_vector("users", |v| {
+                                        v.push_hash(|h| { h.insert_string("name", "Ada").insert_string("role", "admin") })
+                                        .push_hash(|h| { h.insert_string("name", "Grace").insert_string("role", "dev") })
+                                        .push_hash(|h| { h.insert_string("name", "Linus").insert_string("role", "ops") })
+                                    });
 
         let rv = Template::new().render_data(&mut w, &data, &nodes);
         match rv { _ => {} }
 
-        assert_eq!("<h1>heading</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        assert_eq!("<ul><li>Ada (admin)</li><li>Grace (dev)</li><li>Linus (ops)</li></ul>".to_string(), String::from_utf8(w.unwrap()).unwrap());
     }
 
-    // #[test]
-    // fn test_spec_lambdas_interpolation_using_render_text() {
-    //     let mut s = MemStream::new();
-    //     let data = HashBuilder::new()
-    //                 .insert_lambda("lambda", |_| {
-    //                      "world".to_string()
-    //                  });
-    //     let s = rustache::render_text("Hello, {{lambda}}!", data);
-
-    //     assert_eq!("Hello, world!".to_string(), String::from_utf8(s.unwrap()).unwrap());
-    // }
+    #[test]
+    fn test_section_vector_sees_parent_scope() {
+        let data = HashBuilder::new().insert_string("company", "Acme")
+                                     .insert_vector("people", |v| {
+                                        v.push_hash(|h| { h.insert_string("name", "Ada") })
+                                        .push_hash(|h| { h.insert_string("name", "Grace").insert_string("company", "Navy") })
+                                     });
+
+        assert_eq!("Ada of Acme, Grace of Navy, ",
+                   render_str("{{#people}}{{name}} of {{company}}, {{/people}}", &data));
+    }
 
-    // #[test]
-    // fn test_spec_lambdas_inverted_section_using_render_text() {
-    //     let data = HashBuilder::new()
-    //                 .insert_string("static", "static")
-    //                 .insert_lambda("lambda", |_| {
-    //                     "false".to_string()
-    //                 });
+    #[test]
+    fn test_nested_sections_see_each_enclosing_scope() {
+        let data = HashBuilder::new().insert_string("site", "docs")
+                                     .insert_hash("team", |h| {
+                                        h.insert_string("lead", "Ada")
+                                        .insert_vector("members", |v| {
+                                            v.push_hash(|h| {
+                                                h.insert_string("name", "Grace")
+                                                .insert_vector("tags", |v| { v.push_hash(|h| { h.insert_string("tag", "x") }) })
+                                            })
+                                        })
+                                     });
+
+        assert_eq!("Grace/Ada/docs/x-Grace-Ada;",
+                   render_str("{{#team}}{{#members}}{{name}}/{{lead}}/{{site}}/{{#tags}}{{tag}}-{{name}}-{{lead}}{{/tags}};{{/members}}{{/team}}", &data));
+    }
 
-    //     let s = rustache::render_text("<{{^lambda}}{{static}}{{/lambda}}>", data);
+    #[test]
+    fn test_nested_dot_notation_lookups() {
+        let data = HashBuilder::new()
+            .insert_string("c", "top level c")
+            .insert_hash("a", |a| {
+                a.insert_hash("b", |b| {
+                    b.insert_string("c", "three")
+                     .insert_hash("c2", |c| { c.insert_string("d", "four") })
+                })
+            });
+
+        assert_eq!("three", render_str("{{a.b.c}}", &data));
+        assert_eq!("four", render_str("{{a.b.c2.d}}", &data));
+        assert_eq!("", render_str("{{a.x.c2.d}}", &data));
+    }
+
+    #[test]
+    fn test_section_implicit_iterator() {
+        let data = HashBuilder::new()
+            .insert_vector("colors", |builder| { builder.push_string("red").push_string("green") });
+        let nodes = parser::parse_nodes(&compiler::create_tokens("<ul>{{#colors}}<li>{{.}}</li>{{/colors}}</ul>"));
+
+        let mut w = MemStream::new();
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("<ul><li>red</li><li>green</li></ul>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_hash_section_scope() {
+        let data = HashBuilder::new()
+            .insert_string("name", "Ada")
+            .insert_string("city", "Nowhere")
+            .insert_hash("address", |h| {
+                h.insert_string("street", "12 Analytical Row")
+                 .insert_string("city", "London")
+                 .insert_hash("country", |h| { h.insert_string("code", "GB") })
+            });
+
+        assert_eq!("12 Analytical Row, London", render_str("{{#address}}{{street}}, {{city}}{{/address}}", &data));
+        // the hash renders once, still sees the outer data, and its own hashes nest
+        assert_eq!("Ada: London GB|Nowhere", render_str("{{#address}}{{name}}: {{city}} {{#country}}{{code}}{{/country}}{{/address}}|{{city}}", &data));
+        assert_eq!("", render_str("{{^address}}none{{/address}}", &data));
+    }
+
+    #[test]
+    fn test_nested_vectors_of_hashes() {
+        let data = HashBuilder::new()
+            .insert_string("currency", "$")
+            .insert_vector("categories", |v| {
+                v.push_hash(|h| {
+                    h.insert_string("name", "Tea")
+                     .insert_vector("products", |p| {
+                         p.push_hash(|h| { h.insert_string("title", "Green").insert_int("price", 3) })
+                          .push_hash(|h| { h.insert_string("title", "Black").insert_int("price", 4) })
+                     })
+                })
+                .push_hash(|h| {
+                    h.insert_string("name", "Coffee")
+                     .insert_vector("products", |p| {
+                         p.push_hash(|h| { h.insert_string("title", "Espresso").insert_int("price", 2) })
+                          .push_hash(|h| { h.insert_string("title", "Latte").insert_int("price", 5) })
+                     })
+                })
+            });
+
+        let template = "{{#categories}}{{name}}:{{#products}} {{title}} ({{name}}, {{currency}}{{price}}){{/products}};{{/categories}}";
+        assert_eq!("Tea: Green (Tea, $3) Black (Tea, $4);Coffee: Espresso (Coffee, $2) Latte (Coffee, $5);",
+                   render_str(template, &data));
+    }
+
+    #[test]
+    fn test_vector_of_vectors() {
+        let data = HashBuilder::new()
+            .insert_vector("matrix", |v| {
+                v.push_vector(|row| { row.push_int(1).push_int(2) })
+                 .push_vector(|row| { row.push_int(3).push_int(4) })
+                 .push_vector(|row| { row })
+            });
+
+        assert_eq!("[1 2 ][3 4 ][]", render_str("{{#matrix}}[{{#.}}{{.}} {{/.}}]{{/matrix}}", &data));
+        assert_eq!("[][][empty]", render_str("{{#matrix}}[{{^.}}empty{{/.}}]{{/matrix}}", &data));
+        assert_eq!("0:01 1:01 2:", render_str("{{#matrix}}{{@index}}:{{#.}}{{@index}}{{/.}} {{/matrix}}", &data).trim());
+    }
+
+    #[test]
+    fn test_section_loop_index() {
+        let data = HashBuilder::new()
+            .insert_vector("rows", |v| {
+                v.push_hash(|h| { h.insert_string("name", "a") })
+                 .push_hash(|h| { h.insert_string("name", "b") })
+                 .push_hash(|h| { h.insert_string("name", "c") })
+            })
+            .insert_vector("tags", |v| { v.push_string("x").push_string("y") });
+
+        assert_eq!("0:a 1:b 2:c ", render_str("{{#rows}}{{@index}}:{{name}} {{/rows}}", &data));
+        assert_eq!("0=x 1=y ", render_str("{{#tags}}{{@index}}={{.}} {{/tags}}", &data));
+        // a nested loop has its own index
+        assert_eq!("[0: 0 1][1: 0 1][2: 0 1]", render_str("{{#rows}}[{{@index}}:{{#tags}} {{@index}}{{/tags}}]{{/rows}}", &data));
+        assert_eq!("", render_str("{{@index}}", &data));
+    }
+
+    #[test]
+    fn test_section_loop_first_last() {
+        let data = HashBuilder::new()
+            .insert_vector("names", |v| { v.push_string("a").push_string("b").push_string("c") })
+            .insert_vector("users", |v| {
+                v.push_hash(|h| { h.insert_string("name", "Ada") })
+                 .push_hash(|h| { h.insert_string("name", "Grace") })
+            })
+            .insert_vector("one", |v| { v.push_string("solo") });
+
+        assert_eq!("a, b, c", render_str("{{#names}}{{.}}{{^@last}}, {{/@last}}{{/names}}", &data));
+        assert_eq!("[Ada, Grace]", render_str("{{#users}}{{#@first}}[{{/@first}}{{name}}{{^@last}}, {{/@last}}{{#@last}}]{{/@last}}{{/users}}", &data));
+        assert_eq!("<solo>", render_str("{{#one}}{{#@first}}<{{/@first}}{{.}}{{#@last}}>{{/@last}}{{/one}}", &data));
+    }
+
+    #[test]
+    fn test_section_over_scalar_vectors() {
+        let data = HashBuilder::new()
+            .insert_string("sep", " | ")
+            .insert_vector("tags", |builder| { builder.push_string("a").push_string("b&c").push_string("d") })
+            .insert_vector("numbers", |builder| { builder.push_int(1).push_float(2.5).push_int(-3) });
+
+        assert_eq!("[a | b&amp;c | d | ]", render_str("[{{#tags}}{{.}}{{sep}}{{/tags}}]", &data));
+        assert_eq!("a b&c d ", render_str("{{#tags}}{{&.}} {{/tags}}", &data));
+        assert_eq!("1, 2.5, -3, ", render_str("{{#numbers}}{{.}}, {{/numbers}}", &data));
+    }
+
+    #[test]
+    fn test_numeric_sections() {
+        let data = HashBuilder::new()
+            .insert_int("count", 3)
+            .insert_int("zero", 0)
+            .insert_float("price", 2.5);
+
+        assert_eq!("[3]", render_str("{{#count}}[{{count}}]{{/count}}{{^count}}none{{/count}}", &data));
+        assert_eq!("[0]", render_str("{{#zero}}[{{zero}}]{{/zero}}{{^zero}}none{{/zero}}", &data));
+        assert_eq!("<3>", render_str("{{#count}}<{{.}}>{{/count}}", &data));
+        assert_eq!("$2.5", render_str("{{#price}}${{price}}{{/price}}{{^price}}free{{/price}}", &data));
+        assert_eq!("none", render_str("{{#missing}}[{{missing}}]{{/missing}}{{^missing}}none{{/missing}}", &data));
+    }
+
+    #[test]
+    fn test_dev_only_region() {
+        let template = "<p>{{name}}</p>{{!-- dev-only --}}<pre>{{#debug}}{{name}}{{/debug}}</pre>{{!--/dev-only--}}";
+        let dev = HashBuilder::new().insert_string("name", "Ada").insert_bool("debug", true);
+        let prod = HashBuilder::new().insert_string("name", "Ada").insert_bool("debug", true)
+                                     .set_production(true);
+
+        assert_eq!("<p>Ada</p><pre>Ada</pre>".to_string(), render_str(template, &dev));
+        assert_eq!("<p>Ada</p>".to_string(), render_str(template, &prod));
+    }
+
+    #[test]
+    fn test_standalone_set_delimiters() {
+        let data = HashBuilder::new().insert_string("name", "Ada");
+
+        assert_eq!("Begin.\nAda\nEnd.".to_string(), render_str("Begin.\n  {{=@ @=}}\n@name@\nEnd.", &data));
+    }
+
+    #[test]
+    fn test_standalone_partial_indentation() {
+        let data = HashBuilder::new().insert_string("first", "a\nb")
+                                     .insert_string("second", "c")
+                                     .set_partials_path("test_data");
+
+        let rv = render_str("items:\n    {{> two_lines.partial }}\nend", &data);
+
+        assert_eq!("items:\n    first: a\nb\n    second: c\nend".to_string(), rv);
+    }
+
+    fn render_str(template: &str, data: &HashBuilder) -> String {
+        let mut w = MemStream::new();
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+
+        let rv = Template::new().render_data(&mut w, data, &nodes);
+        match rv { _ => {} }
+
+        String::from_utf8(w.unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_inverted_section_absent_key() {
+        let data = HashBuilder::new().insert_bool("outer", true);
+        assert_eq!("No items", render_str("{{^items}}No items{{/items}}", &data));
+        assert_eq!("No items", render_str("{{#outer}}{{^items}}No items{{/items}}{{/outer}}", &data));
+    }
+
+    #[test]
+    fn test_inverted_section_false_bool() {
+        let data = HashBuilder::new().insert_bool("outer", true).insert_bool("items", false);
+        assert_eq!("No items", render_str("{{^items}}No items{{/items}}", &data));
+        assert_eq!("No items", render_str("{{#outer}}{{^items}}No items{{/items}}{{/outer}}", &data));
+    }
+
+    #[test]
+    fn test_inverted_section_empty_vector() {
+        let data = HashBuilder::new().insert_bool("outer", true).insert_vector("items", |v| { v });
+        assert_eq!("No items", render_str("{{^items}}No items{{/items}}", &data));
+        assert_eq!("No items", render_str("{{#outer}}{{^items}}No items{{/items}}{{/outer}}", &data));
+    }
+
+    #[test]
+    fn test_inverted_section_populated_vector() {
+        let data = HashBuilder::new().insert_bool("outer", true)
+                                     .insert_vector("items", |v| { v.push_string("one") })
+                                     .insert_bool("flag", true);
+        assert_eq!("", render_str("{{^items}}No items{{/items}}", &data));
+        assert_eq!("", render_str("{{#outer}}{{^items}}No items{{/items}}{{/outer}}", &data));
+        assert_eq!("", render_str("{{#outer}}{{^flag}}No flag{{/flag}}{{/outer}}", &data));
+    }
+
+    #[test]
+    fn test_bool_section_renders_once() {
+        let template = "{{#visible}}Hello{{/visible}}";
+
+        let data = HashBuilder::new().insert_bool("visible", true);
+        assert_eq!("Hello", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_bool("visible", false);
+        assert_eq!("", render_str(template, &data));
+
+        let data = HashBuilder::new();
+        assert_eq!("", render_str(template, &data));
+    }
+
+    #[test]
+    fn test_nested_bool_section_renders_once() {
+        let template = "{{#outer}}[{{#visible}}Hello{{/visible}}]{{/outer}}";
+
+        let data = HashBuilder::new().insert_bool("outer", true).insert_bool("visible", true);
+        assert_eq!("[Hello]", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_bool("outer", true).insert_bool("visible", false);
+        assert_eq!("[]", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_bool("outer", true);
+        assert_eq!("[]", render_str(template, &data));
+    }
+
+    #[test]
+    fn test_inverted_dotted_section_missing_at_each_level() {
+        let template = "{{^a.b.c}}missing{{/a.b.c}}";
+
+        let data = HashBuilder::new();
+        assert_eq!("missing", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_hash("a", |a| { a });
+        assert_eq!("missing", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_hash("a", |a| { a.insert_hash("b", |b| { b }) });
+        assert_eq!("missing", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_hash("a", |a| { a.insert_string("b", "leaf") });
+        assert_eq!("missing", render_str(template, &data));
+    }
+
+    #[test]
+    fn test_inverted_dotted_section_leaf_value() {
+        let template = "{{^a.b.c}}missing{{/a.b.c}}";
+
+        let data = HashBuilder::new().insert_hash("a", |a| {
+            a.insert_hash("b", |b| { b.insert_bool("c", false) })
+        });
+        assert_eq!("missing", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_hash("a", |a| {
+            a.insert_hash("b", |b| { b.insert_bool("c", true) })
+        });
+        assert_eq!("", render_str(template, &data));
+
+        let data = HashBuilder::new().insert_bool("outer", true).insert_hash("a", |a| {
+            a.insert_hash("b", |b| { b.insert_bool("c", true) })
+        });
+        assert_eq!("", render_str("{{#outer}}{{^a.b.c}}missing{{/a.b.c}}{{/outer}}", &data));
+        assert_eq!("missing", render_str("{{#outer}}{{^a.x.c}}missing{{/a.x.c}}{{/outer}}", &data));
+    }
+
+    #[test]
+    fn test_dotted_section_pushes_each_level() {
+        let data = HashBuilder::new().insert_hash("a", |a| {
+            a.insert_hash("b", |b| { b.insert_hash("c", |c| { c.insert_string("name", "deep") }) })
+        });
+        assert_eq!("deep", render_str("{{#a.b.c}}{{name}}{{/a.b.c}}", &data));
+    }
+
+    #[test]
+    fn test_not_escape_html() {
+        let s = "1<2 <b>hello</b>";
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Unescaped("value", "{{ value }}".to_string())];
+        let data = HashBuilder::new().insert_string("value", s);
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!(s, str::from_utf8(w.as_slice()).unwrap());
+    }
+
+    #[test]
+    fn test_render_to_io_stream() {
+        let mut w = MemStream::new();
+        let data = HashBuilder::new().insert_string("value1", "The heading");
+        let nodes: Vec<Node> = vec![Static("<h1>"), Value("value1", "{{ value1 }}".to_string()), Static("</h1>")];
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("<h1>The heading</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_render_to_vec_writer() {
+        let mut w: Vec<u8> = Vec::new();
+        let data = HashBuilder::new().insert_string("name", "Zoë & Łukasz").insert_int("count", 2);
+        let nodes = parser::parse_nodes(&compiler::create_tokens("«{{name}}» × {{count}} ✓"));
+
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+
+        assert_eq!("«Zoë &amp; Łukasz» × 2 ✓", str::from_utf8(&w).unwrap());
+    }
+
+    #[test]
+    fn test_render_to_cursor_writer() {
+        let mut w = io::Cursor::new(Vec::new());
+        let data = HashBuilder::new().insert_vector("words", |v| { v.push_string("日本").push_string("語") });
+        let nodes = parser::parse_nodes(&compiler::create_tokens("{{#words}}[{{.}}]{{/words}}"));
+
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+
+        assert_eq!("[日本][語]".to_string(), String::from_utf8(w.into_inner()).unwrap());
+    }
+
+    #[test]
+    fn test_unescaped_node_correct_bool_false_data() {
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Static("<h1>"), Unescaped("value1", "{{& value1 }}".to_string()), Static("</h1>")];
+        let data = HashBuilder::new().insert_bool("value1", false);
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("<h1>false</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_unescaped_node_correct_bool_true_data() {
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Static("<h1>"), Unescaped("value1", "{{& value1 }}".to_string()), Static("</h1>")];
+        let data = HashBuilder::new().insert_bool("value1", true);
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("<h1>true</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_value_and_unescaped_node_numeric_data() {
+        let mut w = MemStream::new();
+        let tokens = compiler::create_tokens("{{ count }} {{& count }} {{ price }} {{& price }} {{ whole }}");
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_int("count", 5i32)
+                                     .insert_float("price", 1.21f64)
+                                     .insert_float("whole", 3.0f64);
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("5 5 1.21 1.21 3".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_section_value_string_data() {
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Section("value1", vec![Value("value", "{{ value }}".to_string())], false, "{{# value1 }}".to_string(), "{{/ value1 }}".to_string())];
+        let data = HashBuilder::new()
+            .insert_hash("value1", |builder| {
+                builder.insert_string("value", "<Section Value>")
+            });
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("&lt;Section Value&gt;".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_section_multiple_value_string_data() {
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Section("names", vec![Value("name", "{{ name }}".to_string())], false, "{{# names }}".to_string(), "{{/ names }}".to_string())];
+        let data = HashBuilder::new()
+            .insert_hash("names", |builder| {
+                builder.insert_vector("name", |builder| {
+                    builder
+                        .push_string("tom")
+                        .push_string("robert")
+                        .push_string("joe")
+                })
+            });
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+
+        assert_eq!("Err(UnexpectedDataType(expecting text for Value(\"name\", \"{{ name }}\"), found vector data [Strng(\"tom\"), Strng(\"robert\"), Strng(\"joe\")]))".to_string(), format!("{:?}", rv));
+        assert_eq!("".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_value_node_vector_data_is_an_error() {
+        let nodes: Vec<Node> = vec![Static("<p>"), Unescaped("list", "{{& list }}".to_string()), Static("</p>")];
+        let data = HashBuilder::new().insert_vector("list", |builder| { builder.push_int(1).push_int(2) });
+
+        let mut w = MemStream::new();
+        match Template::new().render_data(&mut w, &data, &nodes) {
+            Err(TemplateErrorType(UnexpectedDataType(_))) => { },
+            other => panic!("expected UnexpectedDataType, got {:?}", other)
+        }
+        assert_eq!("<p>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_value_node_hash_data_is_an_error() {
+        let nodes: Vec<Node> = vec![Static("<p>"), Value("user", "{{ user }}".to_string()), Static("</p>")];
+        let data = HashBuilder::new().insert_hash("user", |builder| { builder.insert_string("user", "Ada") });
+
+        let mut w = MemStream::new();
+        match Template::new().render_data(&mut w, &data, &nodes) {
+            Err(TemplateErrorType(UnexpectedDataType(_))) => { },
+            other => panic!("expected UnexpectedDataType, got {:?}", other)
+        }
+        assert_eq!("<p>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    // #[test]
+    // fn test_excessively_nested_data() {
+    //     let mut w = MemStream::new();
+    //     let nodes: Vec<Node> = vec![Section("hr", vec![Section("people", vec![Value("name", "{{ name }}".to_string())], false, "{{# people }}".to_string(), "{{/ people }}".to_string())], false, "{{# hr }}".to_string(), "{{/ hr }}".to_string())];
+    //     let data = HashBuilder::new()
+    //         .insert_hash("hr", |builder| {
+    //             builder.insert_hash("people", |builder| {
+    //                 builder
+    //                     .insert_vector("name", |builder| {
+    //                         builder
+    //                             .push_string("tom")
+    //                             .push_string("robert")
+    //                             .push_string("joe")
+    //                 })
+    //             })
+    //         });
+
+    //     let rv = Template::new().render_data(&mut w, &data, &nodes);
+    //     assert_eq!("tomrobertjoe".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    // }
+
+    #[test]
+    fn test_unescaped_node_lambda_data() {
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Static("<h1>"), Unescaped("func1", "{{& func1 }}".to_string()), Static("</h1>")];
+        let mut f = |_| { "heading".to_string() };
+        let data = HashBuilder::new().insert_lambda("func1", &mut f);
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("<h1>heading</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_value_node_lambda_data() {
+        let mut w = MemStream::new();
+        let nodes: Vec<Node> = vec![Static("<h1>"), Value("func1", "{{ func1 }}".to_string()), Static("</h1>")];
+        let mut f = |_| { "heading".to_string() };
+        let data = HashBuilder::new().insert_lambda("func1", &mut f);
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("<h1>heading</h1>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_section_lambda_raw_text() {
+        let mut seen = Vec::new();
+        {
+            let mut f = |raw: String| { seen.push(raw.clone()); raw.to_uppercase() };
+            let data = HashBuilder::new().insert_lambda("upper", &mut f)
+                                         .insert_string("name", "world");
+            let nodes = parser::parse_nodes(&compiler::create_tokens("<p>{{#upper}}hello {{name}}{{/upper}}</p>"));
+
+            let mut w = MemStream::new();
+            Template::new().render_data(&mut w, &data, &nodes).unwrap();
+            // the uppercased tag no longer matches any data, so it renders empty
+            assert_eq!("<p>HELLO </p>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        }
+
+        // the lambda sees the section's unrendered source
+        assert_eq!(vec!["hello {{name}}".to_string()], seen);
+    }
+
+    #[test]
+    fn test_section_lambda_rerenders_output() {
+        let mut f = |_| { "<b>{{name}}</b>".to_string() };
+        let data = HashBuilder::new().insert_lambda("bold", &mut f)
+                                     .insert_string("name", "World")
+                                     .insert_hash("user", |h| { h.insert_string("name", "Ada") });
+
+        assert_eq!("<b>World</b>", render_str("{{#bold}}ignored{{/bold}}", &data));
+        assert_eq!("<b>Ada</b>", render_str("{{#user}}{{#bold}}{{/bold}}{{/user}}", &data));
+    }
+
+    #[test]
+    fn test_triple_mustache() {
+        let data = HashBuilder::new().insert_string("html", "<b>\"Ada\" & co</b>")
+                                     .insert_hash("user", |h| { h.insert_string("bio", "<i>hi</i>") });
+
+        assert_eq!("<b>\"Ada\" & co</b>", render_str("{{{html}}}", &data));
+        assert_eq!("<b>\"Ada\" & co</b>|<b>\"Ada\" & co</b>", render_str("{{{ html }}}|{{& html }}", &data));
+        assert_eq!("&lt;b&gt;&quot;Ada&quot; &amp; co&lt;/b&gt;", render_str("{{html}}", &data));
+        assert_eq!("<i>hi</i>|<i>hi</i>", render_str("{{{user.bio}}}|{{#user}}{{{bio}}}{{/user}}", &data));
+    }
+
+    #[test]
+    fn test_tag_whitespace() {
+        let data = HashBuilder::new().insert_string("name", "Ada")
+                                     .insert_hash("user", |h| { h.insert_string("role", "admin") });
+
+        assert_eq!("Ada|Ada|Ada", render_str("{{name}}|{{ name }}|{{  name  }}", &data));
+        assert_eq!("Ada|Ada", render_str("{{&name}}|{{&  name  }}", &data));
+        assert_eq!("admin|admin", render_str("{{#user}}{{role}}{{/user}}|{{#  user  }}{{  role }}{{/ user }}", &data));
+
+        // lambdas still see the tags as they were written
+        let mut seen = Vec::new();
+        {
+            let mut f = |raw: String| { seen.push(raw); "".to_string() };
+            let data = HashBuilder::new().insert_lambda("wrap", &mut f);
+            render_str("{{#wrap}}{{  name  }}{{/wrap}}", &data);
+        }
+        assert_eq!(vec!["{{  name  }}".to_string()], seen);
+    }
+
+    #[test]
+    fn test_data_lambda_section() {
+        let mut seen = Vec::new();
+        {
+            let mut f = |raw: String| {
+                seen.push(raw);
+                Vector(vec![Strng("red".to_string()), Strng("green".to_string())])
+            };
+            let mut count = |_| { Integer(2) };
+            let data = HashBuilder::new().insert_data_lambda("colors", &mut f)
+                                         .insert_data_lambda("count", &mut count);
+            let nodes = parser::parse_nodes(&compiler::create_tokens("{{count}}: {{#colors}}<li>{{.}}</li>{{/colors}}"));
+
+            let mut w = MemStream::new();
+            Template::new().render_data(&mut w, &data, &nodes).unwrap();
+            assert_eq!("2: <li>red</li><li>green</li>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        }
+
+        assert_eq!(vec!["<li>{{.}}</li>".to_string()], seen);
+    }
+
+    #[test]
+    fn test_variable_lambda_escaping() {
+        let mut seen = Vec::new();
+        {
+            let mut f = |arg: String| { seen.push(arg); "<b>bold</b>".to_string() };
+            let data = HashBuilder::new().insert_lambda("func1", &mut f);
+
+            let escaped = vec![Value("func1", "{{ func1 }}".to_string())];
+            let mut w = MemStream::new();
+            Template::new().render_data(&mut w, &data, &escaped).unwrap();
+            assert_eq!("&lt;b&gt;bold&lt;/b&gt;".to_string(), String::from_utf8(w.unwrap()).unwrap());
+
+            let unescaped = vec![Unescaped("func1", "{{& func1 }}".to_string())];
+            let mut w = MemStream::new();
+            Template::new().render_data(&mut w, &data, &unescaped).unwrap();
+            assert_eq!("<b>bold</b>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        }
+
+        // plain variable lambdas are passed an empty string
+        assert_eq!(vec!["".to_string(), "".to_string()], seen);
+    }
+
+    // #[test]
+    // fn test_spec_lambdas_interpolation_using_render_text() {
+    //     let mut s = MemStream::new();
+    //     let data = HashBuilder::new()
+    //                 .insert_lambda("lambda", |_| {
+    //                      "world".to_string()
+    //                  });
+    //     let s = rustache::render_text("Hello, {{lambda}}!", data);
+
+    //     assert_eq!("Hello, world!".to_string(), String::from_utf8(s.unwrap()).unwrap());
+    // }
+
+    // #[test]
+    // fn test_spec_lambdas_inverted_section_using_render_text() {
+    //     let data = HashBuilder::new()
+    //                 .insert_string("static", "static")
+    //                 .insert_lambda("lambda", |_| {
+    //                     "false".to_string()
+    //                 });
+
+    //     let s = rustache::render_text("<{{^lambda}}{{static}}{{/lambda}}>", data);
 
     //     assert_eq!("<>".to_string(), String::from_utf8(s.unwrap()).unwrap());
     // }
@@ -922,7 +2679,7 @@ mod template_tests {
     #[test]
     fn test_partial_node_correct_data() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Static("A wise woman once said: "), Part("hopper_quote.partial", "{{> hopper_quote.partial }}")];
+        let nodes: Vec<Node> = vec![Static("A wise woman once said: "), Part("hopper_quote.partial", "{{> hopper_quote.partial }}", "")];
         let data = HashBuilder::new().insert_string("author", "Grace Hopper")
                                      .set_partials_path("test_data");
 
@@ -938,7 +2695,7 @@ mod template_tests {
     #[test]
     fn test_partial_node_correct_data_with_extra() {
         let mut w = MemStream::new();
-        let nodes: Vec<Node> = vec![Static("A wise woman once said: "), Part("hopper_quote.partial", "{{> hopper_quote.partial }}"), Static(" something else "), Value("extra", "{{ extra }}".to_string())];
+        let nodes: Vec<Node> = vec![Static("A wise woman once said: "), Part("hopper_quote.partial", "{{> hopper_quote.partial }}", ""), Static(" something else "), Value("extra", "{{ extra }}".to_string())];
         let data = HashBuilder::new().insert_string("author", "Grace Hopper")
                                      .insert_string("extra", "extra data")
                                      .set_partials_path("test_data");
@@ -952,6 +2709,48 @@ mod template_tests {
         assert_eq!(s, String::from_utf8(w.unwrap()).unwrap());
     }
 
+    #[test]
+    fn test_partial_node_backslash_path() {
+        let data = HashBuilder::new().insert_string("name", "Grace")
+                                     .set_partials_path("test_data");
+        let nodes = parser::parse_nodes(&compiler::create_tokens("{{> nested\\hello.partial }}"));
+
+        let mut w = MemStream::new();
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("Hello, Grace!".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_partial_node_slash_path_on_windows() {
+        let data = HashBuilder::new().insert_string("name", "Grace")
+                                     .set_partials_path("test_data");
+        let nodes = parser::parse_nodes(&compiler::create_tokens("{{> nested/hello.partial }}"));
+
+        let mut w = MemStream::new();
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("Hello, Grace!".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_partial_node_with_args() {
+        let data = HashBuilder::new().insert_string("greeting", "Hello")
+                                     .insert_string("user", "Grace")
+                                     .set_partials_path("test_data");
+
+        let literal = "{{> greeting.partial name=\"World\" }}";
+        let mut w = MemStream::new();
+        let nodes = parser::parse_nodes(&compiler::create_tokens(literal));
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("Hello, World!".to_string(), String::from_utf8(w.unwrap()).unwrap());
+
+        let key = "{{> greeting.partial name=user }}";
+        let mut w = MemStream::new();
+        let nodes = parser::parse_nodes(&compiler::create_tokens(key));
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("Hello, Grace!".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
     #[test]
     fn test_section_node_partial_node_correct_data() {
         let mut w = MemStream::new();
@@ -983,6 +2782,338 @@ mod template_tests {
         assert_eq!(completed.is_ok(), true);
     }
 
+    #[test]
+    fn test_section_else_uses_outer_scope() {
+        let mut w = MemStream::new();
+        let tokens = compiler::create_tokens("{{#items}}<li>{{name}}</li>{{:empty}}No items for {{owner}}{{/items}}");
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_vector("items", |v| { v })
+                                     .insert_string("owner", "Grace");
+
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        match rv { _ => {} }
+
+        assert_eq!("No items for Grace".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    fn render_strict(template: &str, strict_variables: bool, strict_sections: bool) -> Result<String, String> {
+        let mut w = MemStream::new();
+        let tokens = compiler::create_tokens(template);
+        let nodes = parser::parse_nodes(&tokens);
+        let data = HashBuilder::new().insert_string("name", "Phil")
+                                     .set_strict_variables(strict_variables)
+                                     .set_strict_sections(strict_sections);
+
+        match Template::new().render_data(&mut w, &data, &nodes) {
+            Ok(_) => Ok(String::from_utf8(w.unwrap()).unwrap()),
+            Err(err) => Err(format!("{:?}", err))
+        }
+    }
+
+    #[test]
+    fn test_lenient_variables_lenient_sections() {
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{name}} {{missing}}", false, false));
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{name}} {{#missing}}x{{/missing}}", false, false));
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{name}} {{missing.b}}", false, false));
+    }
+
+    #[test]
+    fn test_strict_variables_lenient_sections() {
+        assert_eq!(Err("UndefinedVariable(missing)".to_string()), render_strict("{{name}} {{missing}}", true, false));
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{name}} {{#missing}}x{{/missing}}", true, false));
+        assert_eq!(Err("UndefinedVariable(missing)".to_string()), render_strict("{{name}} {{missing.b}}", true, false));
+    }
+
+    #[test]
+    fn test_lenient_variables_strict_sections() {
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{name}} {{missing}}", false, true));
+        assert_eq!(Err("UndefinedSection(missing)".to_string()), render_strict("{{name}} {{#missing}}x{{/missing}}", false, true));
+        assert_eq!(Err("UndefinedSection(missing)".to_string()), render_strict("{{name}} {{^missing}}x{{/missing}}", false, true));
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{name}} {{missing.b}}", false, true));
+    }
+
+    #[test]
+    fn test_strict_variables_strict_sections() {
+        assert_eq!(Err("UndefinedVariable(missing)".to_string()), render_strict("{{name}} {{missing}}", true, true));
+        assert_eq!(Err("UndefinedSection(missing)".to_string()), render_strict("{{name}} {{#missing}}x{{/missing}}", true, true));
+        assert_eq!(Err("UndefinedVariable(other)".to_string()), render_strict("{{#name}}{{other}}{{/name}}", true, true));
+        assert_eq!(Err("UndefinedVariable(missing)".to_string()), render_strict("{{name}} {{missing.b}}", true, true));
+    }
+
+    #[test]
+    fn test_strict_variables_unescaped() {
+        assert_eq!(Ok("Phil ".to_string()), render_strict("{{&name}} {{&missing}}", false, false));
+        assert_eq!(Err("UndefinedVariable(missing)".to_string()), render_strict("{{&name}} {{&missing}}", true, false));
+        assert_eq!(Err("UndefinedVariable(missing)".to_string()), render_strict("{{#name}}{{&missing}}{{/name}}", true, false));
+    }
+
+    // a writer that accepts a fixed number of bytes, then fails every write
+    struct FailingWriter {
+        remaining: usize
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if buf.len() > self.remaining {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe"));
+            }
+            self.remaining -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_errors_are_returned() {
+        let data = HashBuilder::new().insert_string("name", "Grace Hopper");
+        let nodes = parser::parse_nodes(&compiler::create_tokens("Hello, {{name}}! Goodbye."));
+
+        let mut w = FailingWriter { remaining: 10 };
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        assert_eq!("Err(StreamWriteError(broken pipe: render: unescaped node string fail))".to_string(), format!("{:?}", rv));
+
+        let mut w = FailingWriter { remaining: 100 };
+        assert!(Template::new().render_data(&mut w, &data, &nodes).is_ok());
+    }
+
+    #[test]
+    fn test_html_minify() {
+        let template = "<div>\n  <!-- navigation -->\n  <p>Hi   {{name}}</p>\n  <PRE>  keep\n   this </PRE>\n  <textarea>a  <!-- b --></textarea>\n</div>\n";
+        let nodes = parser::parse_nodes(&compiler::create_tokens(template));
+        let data = HashBuilder::new().insert_string("name", "there").set_html_minify(true);
+
+        let mut w = MemStream::new();
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("<div><p>Hi there</p><PRE>  keep\n   this </PRE><textarea>a  <!-- b --></textarea></div>".to_string(),
+                   String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_html_minify_inline_and_scripts() {
+        let data = HashBuilder::new().insert_string("name", "there").set_html_minify(true);
+
+        assert_eq!("<p><b>a</b> <i>there</i> c</p>",
+                   render_str("<p>\n  <b>a</b>\n  <!-- x -->\n  <i>{{name}}</i>  c\n</p>\n", &data));
+        assert_eq!("<div><SCRIPT>// setup\nvar s = \"<!--\";\n</script><style>a  { }</STYLE></div>",
+                   render_str("<div>\n <SCRIPT>// setup\nvar s = \"<!--\";\n</script>\n <style>a  { }</STYLE>\n</div>", &data));
+    }
+
+    #[test]
+    fn test_final_newline() {
+        let render = |template: &str, policy: FinalNewline| {
+            render_str(template, &HashBuilder::new().insert_string("name", "Ada").set_final_newline(policy))
+        };
+
+        assert_eq!("Ada", render("{{name}}", FinalNewline::Keep));
+        assert_eq!("Ada\n", render("{{name}}\n", FinalNewline::Keep));
+        assert_eq!("Ada\n\n\n", render("{{name}}\n\n\n", FinalNewline::Keep));
+
+        assert_eq!("Ada\n", render("{{name}}", FinalNewline::Ensure));
+        assert_eq!("Ada\n", render("{{name}}\n", FinalNewline::Ensure));
+        assert_eq!("Ada\n", render("{{name}}\n\n\n", FinalNewline::Ensure));
+        assert_eq!("Ada\r\n", render("{{name}}\r\n\r\n", FinalNewline::Ensure));
+
+        assert_eq!("Ada", render("{{name}}", FinalNewline::Strip));
+        assert_eq!("Ada", render("{{name}}\n", FinalNewline::Strip));
+        assert_eq!("Ada", render("{{name}}\n\n\n", FinalNewline::Strip));
+    }
+
+    #[test]
+    fn test_float_precision() {
+        let data = HashBuilder::new().insert_float("pi", 3.14159).insert_float("whole", 3.0);
+        let template = "{{pi}} {{&pi}} {{whole}}";
+
+        assert_eq!("3.14159 3.14159 3", render_str(template, &data));
+        assert_eq!("3.14 3.14 3.00", render_str(template, &data.clone().set_float_precision(2)));
+        assert_eq!("3 3 3", render_str(template, &data.clone().set_float_precision(0)));
+    }
+
+    #[test]
+    fn test_max_output_bytes() {
+        let data = HashBuilder::new()
+            .insert_vector("rows", |mut builder| {
+                for _ in 0..1000 {
+                    builder = builder.push_hash(|row| { row.insert_string("cell", "0123456789") });
+                }
+                builder
+            })
+            .set_max_output_bytes(64);
+        let nodes = parser::parse_nodes(&compiler::create_tokens("{{#rows}}<td>{{cell}}</td>{{/rows}}"));
+
+        let mut w = MemStream::new();
+        let rv = Template::new().render_data(&mut w, &data, &nodes);
+        assert_eq!("Err(OutputLimitExceeded(more than 64 bytes))".to_string(), format!("{:?}", rv));
+        assert!(w.unwrap().len() <= 64);
+
+        let data = HashBuilder::new().insert_string("cell", "0123456789").set_max_output_bytes(64);
+        let nodes = parser::parse_nodes(&compiler::create_tokens("<td>{{cell}}</td>"));
+        let mut w = MemStream::new();
+        Template::new().render_data(&mut w, &data, &nodes).unwrap();
+        assert_eq!("<td>0123456789</td>".to_string(), String::from_utf8(w.unwrap()).unwrap());
+    }
+
+    #[test]
+    fn test_on_tag_hook() {
+        let mut seen: Vec<String> = Vec::new();
+        {
+            let mut w = MemStream::new();
+            let tokens = compiler::create_tokens("{{title}}{{#people}}{{&name}}{{/people}}{{^empty}}{{> greeting.partial}}{{/empty}}");
+            let nodes = parser::parse_nodes(&tokens);
+            let mut hook = |name: &str, kind: TagKind| { seen.push(format!("{:?}:{}", kind, name)); };
+            let data = HashBuilder::new()
+                .insert_string("title", "People")
+                .insert_string("greeting", "Hi")
+                .insert_vector("people", |v| {
+                    v.push_hash(|h| { h.insert_string("name", "Ada") })
+                     .push_hash(|h| { h.insert_string("name", "Grace") })
+                })
+                .set_partials_path("test_data")
+                .set_on_tag(&mut hook);
+
+            let rv = Template::new().render_data(&mut w, &data, &nodes);
+            assert!(rv.is_ok());
+            assert_eq!("PeopleAdaGraceHi, !".to_string(), String::from_utf8(w.unwrap()).unwrap());
+        }
+
+        assert_eq!(vec!["Variable:title", "Section:people", "Unescaped:name", "Unescaped:name",
+                        "InvertedSection:empty", "Partial:greeting.partial", "Variable:greeting", "Variable:name"], seen);
+    }
+
+    #[test]
+    fn test_repeat_section() {
+        let data = HashBuilder::new().insert_string("label", "row");
+
+        assert_eq!("<li>row 0</li><li>row 1</li><li>row 2</li>",
+                   render_str("{{#repeat 3}}<li>{{label}} {{@index}}</li>{{/repeat}}", &data));
+        assert_eq!("<0,1,2>", render_str("{{#repeat 3}}{{#@first}}<{{/@first}}{{@index}}{{^@last}},{{/@last}}{{#@last}}>{{/@last}}{{/repeat}}", &data));
+        assert_eq!("row row ", render_str("{{=<% %>=}}<%#repeat 2%><%label%> <%/repeat%>", &data));
+    }
+
+    #[test]
+    fn test_each_section_insertion_order() {
+        let data = HashBuilder::new()
+            .insert_hash("stats", |h| {
+                h.insert_int("str", 18).insert_int("dex", 12).insert_int("con", 14)
+                 .insert_int("int", 9).insert_int("wis", 11).insert_int("cha", 16)
+            })
+            .insert_hash("user", |h| {
+                h.insert_hash("pets", |h| {
+                    h.insert_hash("rex", |h| { h.insert_string("kind", "dog") })
+                     .insert_hash("ada", |h| { h.insert_string("kind", "cat") })
+                })
+            });
+
+        assert_eq!("str=18, dex=12, con=14, int=9, wis=11, cha=16",
+                   render_str("{{#each stats}}{{@key}}={{@value}}{{^@last}}, {{/@last}}{{/each}}", &data));
+        assert_eq!("0:rex the dog 1:ada the cat ",
+                   render_str("{{#user}}{{#each pets}}{{@index}}:{{@key}} the {{#@value}}{{kind}}{{/@value}} {{/each}}{{/user}}", &data));
+        assert_eq!("rex ada ", render_str("{{#each user.pets}}{{@key}} {{/each}}", &data));
+        assert_eq!("", render_str("{{#each missing}}{{@key}}{{/each}}", &data));
+    }
+
+    #[test]
+    fn test_lambda_panic_is_caught() {
+        let mut boom = |_: String| -> String { panic!("boom") };
+        let data = HashBuilder::new()
+            .insert_lambda("boom", &mut boom)
+            .set_catch_lambda_panics(true);
+
+        for template in vec!["a {{boom}} b", "a {{#boom}}x{{/boom}} b"] {
+            let nodes = parser::parse_nodes(&compiler::create_tokens(template));
+            let mut w = MemStream::new();
+            match Template::new().render_data(&mut w, &data, &nodes) {
+                Err(TemplateErrorType(LambdaPanicked(msg))) => assert_eq!("lambda boom panicked", msg),
+                other => panic!("expected a LambdaPanicked error, got {:?}", other)
+            }
+        }
+    }
+
+    #[test]
+    fn test_custom_escape_fn() {
+        fn escape_quotes(input: &str) -> String {
+            input.replace("\\", "\\\\").replace("\"", "\\\"").replace("'", "\\'")
+        }
+        let data = HashBuilder::new().insert_string("quote", "say \"hi\" <b>'now'</b>")
+                                     .set_escape_mode(EscapeMode::Xml)
+                                     .set_escape_fn(escape_quotes);
+
+        assert_eq!("{ \"q\": \"say \\\"hi\\\" <b>\\'now\\'</b>\" } say \"hi\" <b>'now'</b>",
+                   render_str("{ \"q\": \"{{quote}}\" } {{&quote}}", &data));
+
+        // closures can capture what they need, and a clone leaves them out
+        let mask = "*";
+        let data = HashBuilder::new().insert_string("pin", "1234")
+                                     .set_escape_fn(move |input: &str| input.chars().map(|_| mask).collect());
+
+        assert_eq!("****", render_str("{{pin}}", &data));
+        assert_eq!("1234", render_str("{{pin}}", &data.clone()));
+    }
+
+    #[test]
+    fn test_escape_slash() {
+        let data = HashBuilder::new().insert_string("code", "</script><a href='/x'>");
+        let template = "{{code}}|{{&code}}";
+
+        assert_eq!("&lt;/script&gt;&lt;a href=&#39;/x&#39;&gt;|</script><a href='/x'>", render_str(template, &data));
+        assert_eq!("&lt;&#x2F;script&gt;&lt;a href=&#39;&#x2F;x&#39;&gt;|</script><a href='/x'>",
+                   render_str(template, &data.clone().set_escape_slash(true)));
+    }
+
+    #[test]
+    fn test_empty_strings_falsy() {
+        let template = "{{#name}}name: [{{name}}]{{/name}}{{^name}}no name{{/name}}";
+        let data = HashBuilder::new().insert_string("name", "");
+
+        assert_eq!("name: []", render_str(template, &data));
+        assert_eq!("no name", render_str(template, &data.clone().set_empty_strings_falsy(true)));
+        assert_eq!("name: [Ada]", render_str(template, &HashBuilder::new().insert_string("name", "Ada")
+                                                                              .set_empty_strings_falsy(true)));
+    }
+
+    #[test]
+    fn test_custom_truthiness() {
+        fn truthy(data: &Data) -> bool {
+            match *data {
+                Strng(ref val) => val != "false",
+                Integer(_) => true,
+                _ => default_truthiness(data)
+            }
+        }
+        let template = "{{#zero}}zero{{/zero}} {{#off}}off{{/off}} {{^off}}not off{{/off}}";
+        let data = HashBuilder::new().insert_int("zero", 0).insert_string("off", "false");
+
+        assert_eq!("zero off ", render_str(template, &data));
+        assert_eq!("zero  not off", render_str(template, &data.clone().set_truthiness(truthy)));
+
+        fn falsy_zero(data: &Data) -> bool {
+            match *data {
+                Integer(0) => false,
+                _ => default_truthiness(data)
+            }
+        }
+        let template = "{{#count}}{{count}} items{{/count}}{{^count}}no items{{/count}}";
+        let data = HashBuilder::new().insert_int("count", 0).set_truthiness(falsy_zero);
+
+        assert_eq!("no items", render_str(template, &data));
+        assert_eq!("3 items", render_str(template, &data.insert_int("count", 3)));
+    }
+
+    #[test]
+    fn test_transform_hook() {
+        let mut upper = |val: &str| val.to_uppercase();
+        let data = HashBuilder::new()
+            .insert_string("name", "ada & grace")
+            .insert_int("count", 2)
+            .insert_vector("tags", |v| { v.push_string("rust") })
+            .set_transform(&mut upper);
+
+        assert_eq!("ADA &amp; GRACE, ADA & GRACE, 2, RUST",
+                   render_str("{{name}}, {{&name}}, {{count}}, {{#tags}}{{.}}{{/tags}}", &data));
+    }
+
   // - name: Interpolation - Multiple Calls
   //   desc: Interpolated lambdas should not be cached.
   //   data:
@@ -0,0 +1,25 @@
+extern crate rustache;
+
+use rustache::{HashBuilder, EscapeMode};
+
+// The default escape mode is process-wide, so it is exercised in its own
+// test binary to keep it from leaking into other renders.
+#[test]
+fn test_default_escape_mode() {
+    assert_eq!(EscapeMode::Html, rustache::default_escape_mode());
+
+    rustache::set_default_escape_mode(EscapeMode::Markdown);
+    assert_eq!(EscapeMode::Markdown, rustache::default_escape_mode());
+
+    let data = HashBuilder::new().insert_string("value", "*bold* <b>");
+    let rv = rustache::render_text("{{ value }}", data);
+    assert_eq!("\\*bold\\* \\<b\\>".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+
+    // an explicit mode on the data always wins over the default
+    let data = HashBuilder::new().insert_string("value", "*bold* <b>")
+                                 .set_escape_mode(EscapeMode::Html);
+    let rv = rustache::render_text("{{ value }}", data);
+    assert_eq!("*bold* &lt;b&gt;".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+
+    rustache::set_default_escape_mode(EscapeMode::Html);
+}
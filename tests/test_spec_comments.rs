@@ -87,26 +87,26 @@ fn test_spec_standalone_line_ending_comment() {
 //   data: { }
 //   template: "  {{! I'm Still Standalone }}\n!"
 //   expected: "!"
-// #[test]
-// fn test_spec_standalone_without_prev_line_comment() {
-//     let data = HashBuilder::new();
-//     let rv = rustache::render_text("  {{! I'm Still Standalone }}\n!", data);
+#[test]
+fn test_spec_standalone_without_prev_line_comment() {
+    let data = HashBuilder::new();
+    let rv = rustache::render_text("  {{! I'm Still Standalone }}\n!", data);
 
-//     assert_eq!("!".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("!".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Standalone Without Newline
 //   desc: Standalone tags should not require a newline to follow them.
 //   data: { }
 //   template: "!\n  {{! I'm Still Standalone }}"
 //   expected: "!\n"
-// #[test]
-// fn test_spec_standalone_without_newline_comment() {
-//     let data = HashBuilder::new();
-//     let rv = rustache::render_text("!\n  {{! I'm Still Standalone }}", data);
+#[test]
+fn test_spec_standalone_without_newline_comment() {
+    let data = HashBuilder::new();
+    let rv = rustache::render_text("!\n  {{! I'm Still Standalone }}", data);
 
-//     assert_eq!("!\n".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("!\n".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Multiline Standalone
 //   desc: All standalone comment lines should be removed.
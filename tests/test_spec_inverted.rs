@@ -143,20 +143,20 @@ fn test_spec_inverted_missing_falsey() {
 //   data: { a: { b: { c: true } } }
 //   template: '"{{^a.b.c}}Not Here{{/a.b.c}}" == ""'
 //   expected: '"" == ""'
-// #[test]
-// fn test_spec_truthy_dotted_names_valid_inverted_section_tags() {
-//     let data = HashBuilder::new()
-//         .insert_hash("a", |builder| {
-//             builder
-//                 .insert_hash("b", |builder| {
-//                     builder
-//                         .insert_bool("c", true)
-//                 })
-//         });
-//  let rv = rustache::render_text("'{{^a.b.c}}Not Here{{/a.b.c}}' == ''", data)
-
-//     assert_eq!("'' == ''".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+#[test]
+fn test_spec_truthy_dotted_names_valid_inverted_section_tags() {
+    let data = HashBuilder::new()
+        .insert_hash("a", |builder| {
+            builder
+                .insert_hash("b", |builder| {
+                    builder
+                        .insert_bool("c", true)
+                })
+        });
+    let rv = rustache::render_text("'{{^a.b.c}}Not Here{{/a.b.c}}' == ''", data);
+
+    assert_eq!("'' == ''".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Dotted Names - Falsey
 //   desc: Dotted names should be valid for Inverted Section tags.
@@ -237,14 +237,14 @@ fn test_spec_inverted_indented_inline_sections() {
 //     | This Is
 //     |
 //     | A Line
-// #[test]
-// fn test_spec_inverted_standalone_lines() {
-//     let data = HashBuilder::new().insert_bool("boolean", false);
+#[test]
+fn test_spec_inverted_standalone_lines() {
+    let data = HashBuilder::new().insert_bool("boolean", false);
 
-//     let rv = rustache::render_text("| This Is\n{{^boolean}}\n|\n{{/boolean}}\n| A Line", data)
+    let rv = rustache::render_text("| This Is\n{{^boolean}}\n|\n{{/boolean}}\n| A Line", data);
 
-//     assert_eq!("| This Is\n|\n| A Line".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("| This Is\n|\n| A Line".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Standalone Indented Lines
 //   desc: Standalone indented lines should be removed from the template.
@@ -259,56 +259,56 @@ fn test_spec_inverted_indented_inline_sections() {
 //     | This Is
 //     |
 //     | A Line
-// #[test]
-// fn test_spec_inverted_standalone_indented_lines() {
-//     let data = HashBuilder::new().insert_bool("boolean", false);
+#[test]
+fn test_spec_inverted_standalone_indented_lines() {
+    let data = HashBuilder::new().insert_bool("boolean", false);
 
-//     let rv = rustache::render_text("| This Is\n  {{^boolean}}\n|\n  {{/boolean}}\n| A Line", data)
+    let rv = rustache::render_text("| This Is\n  {{^boolean}}\n|\n  {{/boolean}}\n| A Line", data);
 
-//     assert_eq!("| This Is\n|\n| A Line".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("| This Is\n|\n| A Line".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Standalone Line Endings
 //   desc: '"\r\n" should be considered a newline for standalone tags.'
 //   data: { boolean: false }
 //   template: "|\r\n{{^boolean}}\r\n{{/boolean}}\r\n|"
 //   expected: "|\r\n|"
-// #[test]
-// fn test_spec_inverted_standalone_rn_is_linebreak() {
-//     let data = HashBuilder::new().insert_bool("boolean", false);
+#[test]
+fn test_spec_inverted_standalone_rn_is_linebreak() {
+    let data = HashBuilder::new().insert_bool("boolean", false);
 
-//     let rv = rustache::render_text("|\r\n{{^boolean}}\r\n{{/boolean}}\r\n|", data)
+    let rv = rustache::render_text("|\r\n{{^boolean}}\r\n{{/boolean}}\r\n|", data);
 
-//     assert_eq!("|\r\n|".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("|\r\n|".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Standalone Without Previous Line
 //   desc: Standalone tags should not require a newline to precede them.
 //   data: { boolean: false }
 //   template: "  {{^boolean}}\n^{{/boolean}}\n/"
 //   expected: "^\n/"
-// #[test]
-// fn test_spec_inverted_standalone_without_previous_line() {
-//     let data = HashBuilder::new().insert_bool("boolean", false);
+#[test]
+fn test_spec_inverted_standalone_without_previous_line() {
+    let data = HashBuilder::new().insert_bool("boolean", false);
 
-//     let rv = rustache::render_text("  {{^boolean}}\n^{{/boolean}}\n/", data);
+    let rv = rustache::render_text("  {{^boolean}}\n^{{/boolean}}\n/", data);
 
-//     assert_eq!("^\n/".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("^\n/".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Standalone Without Newline
 //   desc: Standalone tags should not require a newline to follow them.
 //   data: { boolean: false }
 //   template: "^{{^boolean}}\n/\n  {{/boolean}}"
 //   expected: "^\n/\n"
-// #[test]
-// fn test_spec_inverted_standalone_without_newline() {
-//     let data = HashBuilder::new().insert_bool("boolean", false);
+#[test]
+fn test_spec_inverted_standalone_without_newline() {
+    let data = HashBuilder::new().insert_bool("boolean", false);
 
-//     let rv = rustache::render_text("^{{^boolean}}\n/\n  {{/boolean}}", data);
+    let rv = rustache::render_text("^{{^boolean}}\n/\n  {{/boolean}}", data);
 
-//     assert_eq!("^\n/\n".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("^\n/\n".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 // - name: Padding
 //   desc: Superfluous in-tag whitespace should be ignored.
@@ -281,22 +281,22 @@ fn test_spec_sections_failed_context_lookups_are_falsy() {
 //       list: [ 'a', 'b', 'c', 'd', 'e' ]
 //     template: '"{{#list}}({{.}}){{/list}}"'
 //     expected: '"(a)(b)(c)(d)(e)"'
-// #[test]
-// fn test_spec_sections_implicit_iterators_directly_interpolate_strings() {
-//     let data = HashBuilder::new()
-//         .insert_vector("list", |builder| {
-//             builder
-//                 .push_string("a")
-//                 .push_string("b")
-//                 .push_string("c")
-//                 .push_string("d")
-//                 .push_string("e")
-//         });
+#[test]
+fn test_spec_sections_implicit_iterators_directly_interpolate_strings() {
+    let data = HashBuilder::new()
+        .insert_vector("list", |builder| {
+            builder
+                .push_string("a")
+                .push_string("b")
+                .push_string("c")
+                .push_string("d")
+                .push_string("e")
+        });
 
-// let rv = rustache::render_text("{{#list}}({{.}}){{/list}}", data);
+    let rv = rustache::render_text("{{#list}}({{.}}){{/list}}", data);
 
-//     assert_eq!("(a)(b)(c)(d)(e)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("(a)(b)(c)(d)(e)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Implicit Iterator - Integer
 //     desc: Implicit iterators should cast integers to strings and interpolate.
@@ -304,22 +304,22 @@ fn test_spec_sections_failed_context_lookups_are_falsy() {
 //       list: [ 1, 2, 3, 4, 5 ]
 //     template: '"{{#list}}({{.}}){{/list}}"'
 //     expected: '"(1)(2)(3)(4)(5)"'
-// #[test]
-// fn test_spec_sections_implicit_iterators_directly_interpolate_integers() {
-//     let data = HashBuilder::new()
-//         .insert_vector("list", |builder| {
-//             builder
-//                 .push_int(1)
-//                 .push_int(2)
-//                 .push_int(3)
-//                 .push_int(4)
-//                 .push_int(5)
-//         });
+#[test]
+fn test_spec_sections_implicit_iterators_directly_interpolate_integers() {
+    let data = HashBuilder::new()
+        .insert_vector("list", |builder| {
+            builder
+                .push_int(1)
+                .push_int(2)
+                .push_int(3)
+                .push_int(4)
+                .push_int(5)
+        });
 
-//     let rv = rustache::render_text("{{#list}}({{.}}){{/list}}", data);
+    let rv = rustache::render_text("{{#list}}({{.}}){{/list}}", data);
 
-//     assert_eq!("(1)(2)(3)(4)(5)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("(1)(2)(3)(4)(5)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Implicit Iterator - Decimal
 //     desc: Implicit iterators should cast decimals to strings and interpolate.
@@ -327,43 +327,43 @@ fn test_spec_sections_failed_context_lookups_are_falsy() {
 //       list: [ 1.10, 2.20, 3.30, 4.40, 5.50 ]
 //     template: '"{{#list}}({{.}}){{/list}}"'
 //     expected: '"(1.1)(2.2)(3.3)(4.4)(5.5)"'
-// #[test]
-// fn test_spec_sections_implicit_iterators_directly_interpolate_floats() {
-//     let data = HashBuilder::new()
-//         .insert_vector("list", |builder| {
-//             builder
-//                 .push_float(1.10)
-//                 .push_float(2.20)
-//                 .push_float(3.30)
-//                 .push_float(4.40)
-//                 .push_float(5.50)
-//         });
+#[test]
+fn test_spec_sections_implicit_iterators_directly_interpolate_floats() {
+    let data = HashBuilder::new()
+        .insert_vector("list", |builder| {
+            builder
+                .push_float(1.10)
+                .push_float(2.20)
+                .push_float(3.30)
+                .push_float(4.40)
+                .push_float(5.50)
+        });
 
-//     let rv = rustache::render_text("{{#list}}({{.}}){{/list}}", data);
+    let rv = rustache::render_text("{{#list}}({{.}}){{/list}}", data);
 
-//     assert_eq!("(1.1)(2.2)(3.3)(4.4)(5.5)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("(1.1)(2.2)(3.3)(4.4)(5.5)".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Dotted Names - Truthy
 //     desc: Dotted names should be valid for Section tags.
 //     data: { a: { b: { c: true } } }
 //     template: '"{{#a.b.c}}Here{{/a.b.c}}" == "Here"'
 //     expected: '"Here" == "Here"'
-// #[test]
-// fn test_spec_sections_truthy_dotted_names_are_valid_section_tags() {
-//     let data = HashBuilder::new()
-//         .insert_hash("a", |builder| {
-//             builder
-//                 .insert_hash("b", |builder| {
-//                     builder
-//                         .insert_bool("c", true)
-//             })
-//         });
+#[test]
+fn test_spec_sections_truthy_dotted_names_are_valid_section_tags() {
+    let data = HashBuilder::new()
+        .insert_hash("a", |builder| {
+            builder
+                .insert_hash("b", |builder| {
+                    builder
+                        .insert_bool("c", true)
+            })
+        });
 
-//     let rv = rustache::render_text("'{{#a.b.c}}Here{{/a.b.c}}' == 'Here'", data);
+    let rv = rustache::render_text("'{{#a.b.c}}Here{{/a.b.c}}' == 'Here'", data);
 
-//     assert_eq!("'Here' == 'Here'".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("'Here' == 'Here'".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Dotted Names - Falsy
 //     desc: Dotted names should be valid for Section tags.
@@ -461,28 +461,15 @@ fn test_spec_sections_single_line_sections_do_not_alter_surrounding_whitespace()
 //       | This Is
 //       |
 //       | A Line
-// #[test]
-// fn test_spec_sections_standalone_lines_are_removed_from_template() {
-//     let data = HashBuilder::new()
-//         .insert_bool("boolean", true);
+#[test]
+fn test_spec_sections_standalone_lines_are_removed_from_template() {
+    let data = HashBuilder::new()
+        .insert_bool("boolean", true);
 
-//     let rv = rustache::render_text("|
-//                            | This Is
-//                            {{#boolean}}
-//                            |
-//                            {{/boolean}}
-//                            | A Line",
-//                            &data,
-//                            &mut w
-//                          );
+    let rv = rustache::render_text("| This Is\n{{#boolean}}\n|\n{{/boolean}}\n| A Line", data);
 
-//     assert_eq!("|
-//                 | This Is
-//                 |
-//                 | A Line".to_string(),
-//                 String::from_utf8(rv.unwrap().unwrap()).unwrap()
-//               );
-// }
+    assert_eq!("| This Is\n|\n| A Line".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Indented Standalone Lines
 //     desc: Indented standalone lines should be removed from the template.
@@ -497,72 +484,60 @@ fn test_spec_sections_single_line_sections_do_not_alter_surrounding_whitespace()
 //       | This Is
 //       |
 //       | A Line
-// #[test]
-// fn test_spec_sections_indented_standalone_lines_are_removed_from_template() {
-//     let data = HashBuilder::new()
-//         .insert_bool("boolean", true);
+#[test]
+fn test_spec_sections_indented_standalone_lines_are_removed_from_template() {
+    let data = HashBuilder::new()
+        .insert_bool("boolean", true);
 
-//     let rv = rustache::render_text("|
-//                            | This Is
-//                              {{#boolean}}
-//                            |
-//                              {{/boolean}}
-//                            | A Line", 
-//                            &data, 
-//                            &mut w
-//                          );
+    let rv = rustache::render_text("| This Is\n  {{#boolean}}\n|\n  {{/boolean}}\n| A Line", data);
 
-//     assert_eq!("|
-//                 | This Is
-//                 |
-//                 | A Line".to_string(),
-//                 String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("| This Is\n|\n| A Line".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Standalone Line Endings
 //     desc: '"\r\n" should be considered a newline for standalone tags.'
 //     data: { boolean: true }
 //     template: "|\r\n{{#boolean}}\r\n{{/boolean}}\r\n|"
 //     expected: "|\r\n|"
-// #[test]
-// fn test_spec_sections_newline_standalone_tags() {
-//     let data = HashBuilder::new()
-//         .insert_bool("boolean", true);
+#[test]
+fn test_spec_sections_newline_standalone_tags() {
+    let data = HashBuilder::new()
+        .insert_bool("boolean", true);
 
-//     let rv = rustache::render_text("|\r\n{{#boolean}}\r\n{{/boolean}}\r\n|", data);
+    let rv = rustache::render_text("|\r\n{{#boolean}}\r\n{{/boolean}}\r\n|", data);
 
-//     assert_eq!("|\r\n|".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("|\r\n|".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Standalone Without Previous Line
 //     desc: Standalone tags should not require a newline to precede them.
 //     data: { boolean: true }
 //     template: "  {{#boolean}}\n#{{/boolean}}\n/"
 //     expected: "#\n/"
-// #[test]
-// fn test_spec_sections_standalone_tags_do_not_require_preceding_newline() {
-//     let data = HashBuilder::new()
-//         .insert_bool("boolean", true);
+#[test]
+fn test_spec_sections_standalone_tags_do_not_require_preceding_newline() {
+    let data = HashBuilder::new()
+        .insert_bool("boolean", true);
 
-//     let rv = rustache::render_text("  {{#boolean}}\n#{{/boolean}}\n/", data);
+    let rv = rustache::render_text("  {{#boolean}}\n#{{/boolean}}\n/", data);
 
-//     assert_eq!("#\n/".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("#\n/".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Standalone Without Newline
 //     desc: Standalone tags should not require a newline to follow them.
 //     data: { boolean: true }
 //     template: "#{{#boolean}}\n/\n  {{/boolean}}"
 //     expected: "#\n/\n"
-// #[test]
-// fn test_spec_sections_standalone_tags_do_not_require_following_newline() {
-//     let data = HashBuilder::new()
-//         .insert_bool("boolean", true);
+#[test]
+fn test_spec_sections_standalone_tags_do_not_require_following_newline() {
+    let data = HashBuilder::new()
+        .insert_bool("boolean", true);
 
-//     let rv = rustache::render_text("#{{#boolean}}\n/\n  {{/boolean}}", data);
+    let rv = rustache::render_text("#{{#boolean}}\n/\n  {{/boolean}}", data);
 
-//     assert_eq!("#\n/\n".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
-// }
+    assert_eq!("#\n/\n".to_string(), String::from_utf8(rv.unwrap().unwrap()).unwrap());
+}
 
 //   - name: Padding
 //     desc: Superfluous in-tag whitespace should be ignored.